@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use puppy_party::state::Transaction;
+
+// try_decode must reject any malformed params with an error instead of trapping
+fuzz_target!(|params: Vec<u64>| {
+    let _ = Transaction::try_decode(&params);
+});
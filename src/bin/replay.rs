@@ -0,0 +1,121 @@
+//! Re-execute a recorded batch of transactions against a state snapshot.
+//!
+//! cargo run --features native --bin replay -- dump.json
+//!
+//! The dump is a JSON object of the form
+//! `{"state": [{"key": [4 x u64], "data": [u64]}], "transactions": [{"pkey": [4 x u64], "params": [u64], "rand": [4 x u64]}]}`.
+//! For every transaction the error code, the emitted events and the transaction
+//! count are printed, followed by the resulting `GlobalState::snapshot`.
+//! Transactions that do not decode or trap are reported with their reason and
+//! rolled back, the replay goes on with the next one.
+
+use puppy_party::command::decode_error;
+use puppy_party::native;
+use puppy_party::state::{GlobalState, Transaction, GLOBAL_STATE};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+
+#[derive(Deserialize)]
+struct KvPair {
+    key: [u64; 4],
+    data: Vec<u64>,
+}
+
+#[derive(Deserialize)]
+struct RecordedTransaction {
+    pkey: [u64; 4],
+    params: Vec<u64>,
+    rand: [u64; 4],
+}
+
+#[derive(Deserialize)]
+struct Dump {
+    state: Vec<KvPair>,
+    transactions: Vec<RecordedTransaction>,
+}
+
+#[derive(Serialize)]
+struct Event {
+    event: u64,
+    data: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct Outcome {
+    index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<u64>, // omitted for transactions that did not decode or trapped
+    error_name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>, // decode error or panic message
+    txsize: u64,
+    events: Vec<Event>,
+}
+
+// process returns [error, txsize, (event << 32 | len, data...)*]
+fn outcome(index: usize, r: Vec<u64>) -> Outcome {
+    let mut events = vec![];
+    let mut i = 2;
+    while i < r.len() {
+        let len = (r[i] & 0xffffffff) as usize;
+        events.push(Event {
+            event: r[i] >> 32,
+            data: r[i + 1..i + 1 + len].to_vec(),
+        });
+        i += 1 + len;
+    }
+    Outcome {
+        index,
+        error: Some(r[0]),
+        error_name: if r[0] == 0 { "" } else { decode_error(r[0] as u32) },
+        reason: None,
+        txsize: r[1],
+        events,
+    }
+}
+
+fn failure(index: usize, error_name: &'static str, reason: String) -> Outcome {
+    Outcome {
+        index,
+        error: None,
+        error_name,
+        reason: Some(reason),
+        txsize: GLOBAL_STATE.0.borrow().txsize,
+        events: vec![],
+    }
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => payload.downcast_ref::<&str>().map_or("unknown panic".to_string(), |msg| msg.to_string()),
+    }
+}
+
+fn main() {
+    let path = std::env::args().nth(1).expect("usage: replay <dump.json>");
+    let file = std::fs::File::open(&path).expect("can not open dump");
+    let dump: Dump = serde_json::from_reader(file).expect("invalid dump");
+
+    native::load_kvpairs(dump.state.into_iter().map(|kv| (kv.key, kv.data)).collect());
+    // traps are reported per transaction instead
+    panic::set_hook(Box::new(|_| {}));
+    for (index, tx) in dump.transactions.iter().enumerate() {
+        let result = match Transaction::try_decode(&tx.params) {
+            Err(e) => failure(index, "DecodeError", format!("{:?}", e)),
+            Ok(_) => {
+                let checkpoint = native::checkpoint();
+                match panic::catch_unwind(AssertUnwindSafe(|| native::run_transaction(&tx.pkey, &tx.params, &tx.rand))) {
+                    Ok(r) => outcome(index, r),
+                    Err(payload) => {
+                        native::rollback(checkpoint);
+                        failure(index, "Trap", panic_message(payload))
+                    }
+                }
+            }
+        };
+        println!("{}", serde_json::to_string(&result).unwrap());
+    }
+    println!("{}", GlobalState::snapshot());
+}
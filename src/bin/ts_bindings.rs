@@ -0,0 +1,82 @@
+//! Emit the TypeScript bindings (opcodes, errors, event ids and storage
+//! layouts) used by the services in `ts/src`.
+//!
+//! cargo run --features native --bin ts_bindings > ts/src/bindings.ts
+
+use puppy_party::command::EVENT_PAUSE_UPDATE;
+use puppy_party::comment::CommentInfo;
+use puppy_party::config::EVENT_CONFIG_UPDATE;
+use puppy_party::error::PuppyError;
+use puppy_party::lottery::{LotteryDraw, SeedCommitment, EVENT_LOTTERY_PRIZE};
+use puppy_party::meme::{MemeInfo, StakeInfo, MEME_INFO_FIELDS, STAKE_INFO_FIELDS};
+use puppy_party::referral::{EVENT_REFERRAL, EVENT_REFERRAL_REWARD};
+use puppy_party::role::{RoleInfo, ROLES};
+use puppy_party::runtime::{IndexedObject, Position};
+use puppy_party::season::SeasonMemeInfo;
+use puppy_party::state::COMMANDS;
+
+fn main() {
+    let mut ts = String::new();
+    ts.push_str("// Generated by `cargo run --features native --bin ts_bindings`, do not edit.\n\n");
+
+    ts.push_str("// command opcodes\n");
+    for (name, opcode) in COMMANDS {
+        ts.push_str(&format!("export const {} = {}n;\n", name, opcode));
+    }
+
+    ts.push_str("\n// event ids\n");
+    let events = [
+        ("EVENT_POSITION_UPDATE", <StakeInfo as Position<StakeInfo>>::EVENT_NAME),
+        ("EVENT_MEME_UPDATE", <MemeInfo as IndexedObject<MemeInfo>>::EVENT_NAME),
+        ("EVENT_SEASON_MEME", <SeasonMemeInfo as IndexedObject<SeasonMemeInfo>>::EVENT_NAME),
+        ("EVENT_COMMENT", <CommentInfo as IndexedObject<CommentInfo>>::EVENT_NAME),
+        ("EVENT_SEED_COMMITMENT", <SeedCommitment as IndexedObject<SeedCommitment>>::EVENT_NAME),
+        ("EVENT_LOTTERY_DRAW", <LotteryDraw as Position<LotteryDraw>>::EVENT_NAME),
+        ("EVENT_LOTTERY_PRIZE", EVENT_LOTTERY_PRIZE),
+        ("EVENT_REFERRAL", EVENT_REFERRAL),
+        ("EVENT_REFERRAL_REWARD", EVENT_REFERRAL_REWARD),
+        ("EVENT_CONFIG_UPDATE", EVENT_CONFIG_UPDATE),
+        ("EVENT_ROLE_UPDATE", <RoleInfo as Position<RoleInfo>>::EVENT_NAME),
+        ("EVENT_PAUSE_UPDATE", EVENT_PAUSE_UPDATE),
+    ];
+    for (name, id) in events {
+        ts.push_str(&format!("export const {} = {};\n", name, id));
+    }
+
+    ts.push_str("\n// role ids granted with GRANT_ROLE\n");
+    for (name, id) in ROLES {
+        ts.push_str(&format!("export const {} = {}n;\n", name, id));
+    }
+
+    ts.push_str("\n// error codes returned as the first word of a transaction result\n");
+    for e in PuppyError::ALL {
+        ts.push_str(&format!("export const ERROR_{} = {};\n", screaming(e.name()), e.code()));
+    }
+    ts.push_str("\nexport const ERROR_NAMES: Record<number, string> = {\n");
+    for e in PuppyError::ALL {
+        ts.push_str(&format!("  {}: \"{}\",\n", e.code(), e.name()));
+    }
+    ts.push_str("};\n\nexport const ERROR_MESSAGES: Record<number, string> = {\n");
+    for e in PuppyError::ALL {
+        ts.push_str(&format!("  {}: \"{}\",\n", e.code(), e.message()));
+    }
+    ts.push_str("};\n");
+
+    ts.push_str("\n// word layout of the object data carried by events\n");
+    ts.push_str(&format!("export const MEME_INFO_FIELDS = {:?};\n", MEME_INFO_FIELDS));
+    ts.push_str(&format!("export const STAKE_INFO_FIELDS = {:?};\n", STAKE_INFO_FIELDS));
+
+    print!("{}", ts);
+}
+
+// PlayerNotExist -> PLAYER_NOT_EXIST
+fn screaming(name: &str) -> String {
+    let mut r = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i != 0 {
+            r.push('_');
+        }
+        r.push(c.to_ascii_uppercase());
+    }
+    r
+}
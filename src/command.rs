@@ -1,14 +1,26 @@
-use crate::config::{get_action_duration, get_action_reward};
-use crate::meme::{MemeInfo, StakeInfo};
-use zkwasm_rest_convention::{IndexedObject, Position};
-use zkwasm_rust_sdk::require;
+use crate::config::{
+    get_action, get_action_reward, get_comment_size, get_jackpot_contribution, get_ticket_price, RewardKind, RuntimeConfig,
+    ACTION_JUMP, ACTION_LOTTERY, ACTION_NONE, ACTION_POST_COMMENTS, ACTION_SHAKE_FEET, ACTION_SHAKE_HEAD,
+};
+use crate::referral::pay_prize;
+use crate::role::RoleInfo;
+use crate::comment::CommentInfo;
+use crate::lottery::{draw_randomness, pick_prize, LotteryDraw, SeedCommitment, EVENT_LOTTERY_PRIZE};
+use crate::meme::{MemeInfo, MemeStakers, StakeInfo};
+use crate::runtime::{insert_event, IndexedObject, Position};
 use zkwasm_rest_abi::WithdrawInfo;
 use crate::settlement::SettlementInfo;
 use crate::player::{PositionHolder, PuppyPlayer};
-use crate::state::GlobalState;
-use crate::error::*;
+use crate::state::GLOBAL_STATE;
+use crate::state::{
+    BUY_TICKETS, CHECK_IN, CLOSE_SEASON, COLLECT, COMMENT, COMMIT_SEED, DEPOSIT, INSTALL_MEME, INSTALL_PLAYER,
+    GRANT_ROLE, JUMP, LOTTERY, OPEN_SEASON, REVEAL_SEED, REVOKE_ROLE, SET_PAUSE, SHAKE_FEET, SHAKE_HEAD, STAKE, TICK,
+    UNSTAKE, UPDATE_CONFIG, VOTE, WITHDRAW, WITHDRAW_LOTTERY,
+};
+use crate::season::rollover;
+use crate::error::PuppyError;
 
-#[derive (Clone)]
+#[derive (Clone, Debug, PartialEq)]
 pub enum Command {
     // standard activities
     Activity(Activity),
@@ -16,33 +28,141 @@ pub enum Command {
     Withdraw(Withdraw),
     WithdrawLottery(WithdrawLottery),
     Deposit(Deposit),
-    // standard player install and timer
-    InstallPlayer,
+    // standard player install with an optional referrer pid, and timer
+    InstallPlayer(Option<[u64; 2]>),
     InstallMeme(u64),
     Tick,
+    // admin season management
+    Season(SeasonCommand),
+    // admin lottery seed commit and reveal
+    Seed(SeedCommand),
+    // admin runtime config
+    UpdateConfig(UpdateConfig),
+    // super admin role management
+    Role(RoleCommand),
+    // admin emergency pause of player commands
+    SetPause(SetPause),
 }
 
+impl Command {
+    // opcode the command is decoded from, bit of the pause bitmap that blocks it
+    pub fn opcode(&self) -> u64 {
+        match self {
+            Command::Activity(activity) => match activity {
+                Activity::Vote(_) => VOTE,
+                Activity::Stake(..) => STAKE,
+                Activity::Unstake(..) => UNSTAKE,
+                Activity::Collect(_) => COLLECT,
+                Activity::Comment(..) => COMMENT,
+                Activity::Lottery => LOTTERY,
+                Activity::BuyTickets(_) => BUY_TICKETS,
+                Activity::CheckIn => CHECK_IN,
+                Activity::Act(action) => match *action {
+                    ACTION_SHAKE_FEET => SHAKE_FEET,
+                    ACTION_SHAKE_HEAD => SHAKE_HEAD,
+                    ACTION_JUMP => JUMP,
+                    _ => unreachable!(),
+                },
+            },
+            Command::Withdraw(_) => WITHDRAW,
+            Command::WithdrawLottery(_) => WITHDRAW_LOTTERY,
+            Command::Deposit(_) => DEPOSIT,
+            Command::InstallPlayer(_) => INSTALL_PLAYER,
+            Command::InstallMeme(_) => INSTALL_MEME,
+            Command::Tick => TICK,
+            Command::Season(SeasonCommand::Open) => OPEN_SEASON,
+            Command::Season(SeasonCommand::Close) => CLOSE_SEASON,
+            Command::Seed(SeedCommand::Commit(_)) => COMMIT_SEED,
+            Command::Seed(SeedCommand::Reveal(_)) => REVEAL_SEED,
+            Command::UpdateConfig(_) => UPDATE_CONFIG,
+            Command::Role(RoleCommand::Grant(..)) => GRANT_ROLE,
+            Command::Role(RoleCommand::Revoke(..)) => REVOKE_ROLE,
+            Command::SetPause(_) => SET_PAUSE,
+        }
+    }
+
+    /// Encode the command into transaction params, the inverse of `Transaction::decode`.
+    /// Comments are padded with zero bytes to whole words which decode drops again,
+    /// comments with a zero byte are rejected by the handler.
+    pub fn encode(&self, nonce: u64) -> Vec<u64> {
+        let header = |command: u64| (nonce << 16) + command;
+        match self {
+            Command::Activity(activity) => match activity {
+                Activity::Vote(sz) => vec![header(VOTE), *sz as u64],
+                Activity::Stake(sz, amount) => vec![header(STAKE), *sz as u64, *amount],
+                Activity::Unstake(sz, amount) => vec![header(UNSTAKE), *sz as u64, *amount],
+                Activity::Collect(sz) => vec![header(COLLECT), *sz as u64],
+                Activity::Comment(sz, content) => {
+                    let mut params = vec![header(COMMENT), *sz as u64];
+                    for chunk in content.chunks(8) {
+                        let mut bytes = [0u8; 8];
+                        bytes[..chunk.len()].copy_from_slice(chunk);
+                        params.push(u64::from_le_bytes(bytes));
+                    }
+                    if content.is_empty() {
+                        params.push(0);
+                    }
+                    params
+                },
+                Activity::Lottery => vec![header(LOTTERY)],
+                Activity::BuyTickets(amount) => vec![header(BUY_TICKETS), *amount],
+                Activity::CheckIn => vec![header(CHECK_IN)],
+                Activity::Act(action) => match *action {
+                    ACTION_SHAKE_FEET => vec![header(SHAKE_FEET)],
+                    ACTION_SHAKE_HEAD => vec![header(SHAKE_HEAD)],
+                    ACTION_JUMP => vec![header(JUMP)],
+                    _ => unreachable!(),
+                },
+            },
+            Command::Withdraw(cmd) => vec![header(WITHDRAW), 0, cmd.data[0], cmd.data[1], cmd.data[2]],
+            Command::WithdrawLottery(cmd) => vec![header(WITHDRAW_LOTTERY), 0, cmd.data[0], cmd.data[1], cmd.data[2]],
+            Command::Deposit(cmd) => vec![header(DEPOSIT), cmd.data[0], cmd.data[1], 0, cmd.data[2]],
+            Command::InstallPlayer(None) => vec![header(INSTALL_PLAYER)],
+            Command::InstallPlayer(Some(referrer)) => vec![header(INSTALL_PLAYER), referrer[0], referrer[1]],
+            Command::InstallMeme(id) => vec![header(INSTALL_MEME), *id],
+            Command::Tick => vec![header(TICK)],
+            Command::Season(SeasonCommand::Open) => vec![header(OPEN_SEASON)],
+            Command::Season(SeasonCommand::Close) => vec![header(CLOSE_SEASON)],
+            Command::Seed(SeedCommand::Commit(hash)) => {
+                vec![header(COMMIT_SEED), hash[0], hash[1], hash[2], hash[3]]
+            },
+            Command::Seed(SeedCommand::Reveal(seed)) => {
+                vec![header(REVEAL_SEED), seed[0], seed[1], seed[2], seed[3]]
+            },
+            Command::UpdateConfig(cmd) => vec![header(UPDATE_CONFIG), cmd.param, cmd.value],
+            Command::Role(RoleCommand::Grant(role, pid)) => vec![header(GRANT_ROLE), *role, pid[0], pid[1]],
+            Command::Role(RoleCommand::Revoke(role, pid)) => vec![header(REVOKE_ROLE), *role, pid[0], pid[1]],
+            Command::SetPause(cmd) => vec![header(SET_PAUSE), cmd.paused],
+        }
+    }
+
+    // commands a pause can block, admin commands and the tick always go through
+    pub fn pausable(&self) -> bool {
+        matches!(
+            self,
+            Command::Activity(_) | Command::Withdraw(_) | Command::WithdrawLottery(_) | Command::InstallPlayer(_)
+        )
+    }
+}
 
 pub trait CommandHandler {
-    fn handle(&self, pid: &[u64; 2], nonce: u64, rand: &[u64; 4], _counter: u64) -> Result<(), u32>;
+    fn handle(&self, pid: &[u64; 2], nonce: u64, rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError>;
 }
 
-#[derive (Clone)]
+#[derive (Clone, Debug, PartialEq)]
 pub struct Withdraw {
     pub data: [u64; 3],
 }
 
 impl CommandHandler for Withdraw {
-    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), u32> {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
         let mut player = PuppyPlayer::get_from_pid(pid);
         match player.as_mut() {
-            None => Err(ERROR_PLAYER_NOT_EXIST),
+            None => Err(PuppyError::PlayerNotExist),
             Some(player) => {
                 player.check_and_inc_nonce(nonce);
-                let balance = player.data.balance;
-                let amount = (self.data[0] & 0xffffffff) as u32;
-                unsafe { require(balance >= amount) };
-                player.data.balance -= amount;
+                let amount = self.data[0] & 0xffffffff;
+                player.data.cost_balance(amount)?;
                 let withdrawinfo =
                     WithdrawInfo::new(&[self.data[0], self.data[1], self.data[2]], 0);
                 SettlementInfo::append_settlement(withdrawinfo);
@@ -53,22 +173,20 @@ impl CommandHandler for Withdraw {
     }
 }
 
-#[derive (Clone)]
+#[derive (Clone, Debug, PartialEq)]
 pub struct WithdrawLottery {
     pub data: [u64; 3],
 }
 
 impl CommandHandler for WithdrawLottery {
-    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), u32> {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
         let mut player = PuppyPlayer::get_from_pid(pid);
         match player.as_mut() {
-            None => Err(ERROR_PLAYER_NOT_EXIST),
+            None => Err(PuppyError::PlayerNotExist),
             Some(player) => {
                 player.check_and_inc_nonce(nonce);
-                let balance = player.data.lottery_info;
-                let amount = (self.data[0] & 0xffffffff) as u32;
-                unsafe { require(balance >= amount) };
-                player.data.lottery_info -= amount;
+                let amount = self.data[0] & 0xffffffff;
+                player.data.cost_lottery_info(amount)?;
                 let withdrawinfo =
                     WithdrawInfo::new(&[self.data[0], self.data[1], self.data[2]], 1<<8);
                 SettlementInfo::append_settlement(withdrawinfo);
@@ -79,20 +197,20 @@ impl CommandHandler for WithdrawLottery {
     }
 }
 
-#[derive (Clone)]
+#[derive (Clone, Debug, PartialEq)]
 pub struct Deposit {
     pub data: [u64; 3],
 }
 
 impl CommandHandler for Deposit {
-    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), u32> {
-        let mut admin = PuppyPlayer::get_from_pid(pid).unwrap();
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::PlayerNotExist)?;
         admin.check_and_inc_nonce(nonce);
         let mut player = PuppyPlayer::get_from_pid(&[self.data[0], self.data[1]]);
         match player.as_mut() {
-            None => Err(ERROR_PLAYER_NOT_EXIST),
+            None => Err(PuppyError::PlayerNotExist),
             Some(player) => {
-                player.data.ticket += self.data[2] as u32;
+                player.data.add_ticket(self.data[2])?;
                 player.store();
                 admin.store();
                 Ok(())
@@ -101,46 +219,200 @@ impl CommandHandler for Deposit {
     }
 }
 
-#[derive (Clone)]
+#[derive (Clone, Debug, PartialEq)]
+pub enum SeasonCommand {
+    Open,
+    Close,
+}
+
+impl CommandHandler for SeasonCommand {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], counter: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::PlayerNotExist)?;
+        admin.check_and_inc_nonce(nonce);
+        let mut state = GLOBAL_STATE.0.borrow_mut();
+        match self {
+            SeasonCommand::Open => {
+                state.season.open(counter)?;
+            },
+            SeasonCommand::Close => {
+                state.season.close(counter)?;
+                // freeze the leaderboard of the closed season
+                rollover(state.season.id, &state.memes);
+            }
+        }
+        admin.store();
+        Ok(())
+    }
+}
+
+#[derive (Clone, Debug, PartialEq)]
+pub enum SeedCommand {
+    Commit([u64; 4]),
+    Reveal([u64; 4]),
+}
+
+impl CommandHandler for SeedCommand {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::PlayerNotExist)?;
+        admin.check_and_inc_nonce(nonce);
+        let mut state = GLOBAL_STATE.0.borrow_mut();
+        match self {
+            SeedCommand::Commit(hash) => {
+                SeedCommitment::commit(state.lottery_epoch, *hash)?;
+                state.lottery_epoch += 1;
+            },
+            SeedCommand::Reveal(seed) => {
+                SeedCommitment::reveal(state.lottery_epoch, *seed)?;
+            }
+        }
+        admin.store();
+        Ok(())
+    }
+}
+
+#[derive (Clone, Debug, PartialEq)]
+pub struct UpdateConfig {
+    pub param: u64, // one of the config::PARAM_* ids
+    pub value: u64,
+}
+
+impl CommandHandler for UpdateConfig {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::PlayerNotExist)?;
+        admin.check_and_inc_nonce(nonce);
+        RuntimeConfig::update(self.param, self.value)?;
+        admin.store();
+        Ok(())
+    }
+}
+
+#[derive (Clone, Debug, PartialEq)]
+pub enum RoleCommand {
+    Grant(u64, [u64; 2]), // role id, pid
+    Revoke(u64, [u64; 2]),
+}
+
+impl CommandHandler for RoleCommand {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::PlayerNotExist)?;
+        admin.check_and_inc_nonce(nonce);
+        match self {
+            RoleCommand::Grant(role, player) => RoleInfo::set(player, *role, true),
+            RoleCommand::Revoke(role, player) => RoleInfo::set(player, *role, false),
+        }
+        admin.store();
+        Ok(())
+    }
+}
+
+// [old paused bitmap, new paused bitmap]
+pub const EVENT_PAUSE_UPDATE: u64 = 0x0d;
+
+#[derive (Clone, Debug, PartialEq)]
+pub struct SetPause {
+    pub paused: u64, // bit i set pauses opcode i
+}
+
+impl CommandHandler for SetPause {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, _rand: &[u64; 4], _counter: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::PlayerNotExist)?;
+        admin.check_and_inc_nonce(nonce);
+        let old = std::mem::replace(&mut GLOBAL_STATE.0.borrow_mut().paused, self.paused);
+        insert_event(EVENT_PAUSE_UPDATE, &mut vec![old, self.paused]);
+        admin.store();
+        Ok(())
+    }
+}
+
+#[derive (Clone, Debug, PartialEq)]
 pub enum Activity {
     // activities
     Vote(usize),
     Stake(usize, u64),
+    Unstake(usize, u64),
     Collect(usize),
-    Comment(Vec<u8>),
+    Comment(usize, Vec<u8>),
     Lottery,
+    BuyTickets(u64),
+    CheckIn,
+    // puppy action without a meme, one of ACTION_SHAKE_FEET, ACTION_SHAKE_HEAD and ACTION_JUMP
+    Act(u64),
 }
 
 impl CommandHandler for Activity {
-    fn handle(&self, pid: &[u64; 2], nonce: u64, rand: &[u64; 4], counter: u64) -> Result<(), u32> {
+    fn handle(&self, pid: &[u64; 2], nonce: u64, rand: &[u64; 4], counter: u64) -> Result<(), PuppyError> {
         let mut player = PuppyPlayer::get_from_pid(pid);
         match player.as_mut() {
-            None => Err(ERROR_PLAYER_NOT_EXIST),
+            None => Err(PuppyError::PlayerNotExist),
             Some(player) => {
                 match self {
                     Activity::Stake(sz, amount) => {
                         player.check_and_inc_nonce(nonce);
                         let meme_id = *sz as u64;
-                        let (pos, meme) = player.stake(meme_id, *amount as u32, counter)?;
+                        let (pos, meme) = player.stake(meme_id, *amount, counter)?;
+                        player.store();
+                        meme.store();
+                        pos.store();
+                        MemeStakers::track(meme_id, pid, pos.data.stake);
+                        StakeInfo::emit_event(&pid, meme_id, &pos.data);
+                        MemeInfo::emit_event(meme_id, &meme.data);
+                        Ok(())
+                    },
+                    Activity::Unstake(sz, amount) => {
+                        player.check_and_inc_nonce(nonce);
+                        let meme_id = *sz as u64;
+                        let (pos, meme) = player.unstake(meme_id, *amount, counter)?;
                         player.store();
                         meme.store();
                         pos.store();
+                        MemeStakers::track(meme_id, pid, pos.data.stake);
                         StakeInfo::emit_event(&pid, meme_id, &pos.data);
                         MemeInfo::emit_event(meme_id, &meme.data);
                         Ok(())
                     },
+                    Activity::BuyTickets(amount) => {
+                        player.check_and_inc_nonce(nonce);
+                        let cost = amount.checked_mul(get_ticket_price()).ok_or(PuppyError::Overflow)?;
+                        let treasury = GLOBAL_STATE.0.borrow().treasury.checked_add(cost)
+                            .ok_or(PuppyError::Overflow)?;
+                        player.data.cost_balance(cost)?;
+                        player.data.add_ticket(*amount)?;
+                        GLOBAL_STATE.0.borrow_mut().treasury = treasury;
+                        player.store();
+                        Ok(())
+                    },
+                    Activity::Act(id) => {
+                        let action = get_action(*id);
+                        player.data.check_and_update_action_timestamp(counter, *id)?;
+                        player.data.cost_ticket(action.cost)?;
+                        player.data.increase_progress(counter, action.reward);
+                        player.check_and_inc_nonce(nonce);
+                        player.store();
+                        Ok(())
+                    },
+                    Activity::CheckIn => {
+                        player.check_and_inc_nonce(nonce);
+                        player.data.check_in(counter)?;
+                        player.store();
+                        Ok(())
+                    },
                     Activity::Collect(sz) => {
                         player.check_and_inc_nonce(nonce);
                         let meme_id = *sz as u64;
                         let pos = player.collect(meme_id, counter)?;
                         player.store();
                         pos.store();
+                        MemeStakers::track(meme_id, pid, pos.data.stake);
                         StakeInfo::emit_event(&pid, meme_id, &pos.data);
                         Ok(())
                     },
                     Activity::Vote(sz) => {
-                        let action_duration = get_action_duration();
-                        player.data.check_and_update_action_timestamp(counter, action_duration)?;
+                        // votes before the first season are not ranked, between seasons they are closed
+                        let season = GLOBAL_STATE.0.borrow().season;
+                        if season.id != 0 && !season.is_open() {
+                            return Err(PuppyError::SeasonNotOpen);
+                        }
+                        player.data.check_and_update_action_timestamp(counter, ACTION_NONE)?;
                         let action_reward = get_action_reward();
                         player.data.cost_ticket(1)?;
                         player.data.increase_progress(counter,action_reward);
@@ -148,9 +420,12 @@ impl CommandHandler for Activity {
                         let meme_id = *sz as u64;
                         let meme = MemeInfo::get_object(meme_id);
                         match meme {
-                            None => Err(INVALID_MEME_INDEX),
+                            None => Err(PuppyError::InvalidMemeIndex),
                             Some (mut m) => {
                                 m.data.rank += 1;
+                                if season.is_open() {
+                                    m.data.season_rank += 1;
+                                }
                                 m.store();
                                 player.store();
                                 MemeInfo::emit_event(meme_id, &m.data);
@@ -159,29 +434,91 @@ impl CommandHandler for Activity {
                         }
                     },
                     Activity::Lottery => {
-                        // This is the selected player; allow them to open the blind box
-                        if player.data.progress == 1000 {
+                        let mut draw = LotteryDraw::get_or_new_position(pid, 0, LotteryDraw::default());
+                        if draw.data.epoch != 0 {
+                            // open the blind box drawn in an earlier epoch
+                            let commitment = SeedCommitment::get_object(draw.data.epoch).unwrap();
+                            if !commitment.data.revealed {
+                                return Err(PuppyError::LotterySeedNotRevealed);
+                            }
+                            player.check_and_inc_nonce(nonce);
+                            let outcome = draw_randomness(&commitment.data.seed, pid, draw.data.nonce, &draw.data.rand);
+                            let (tier, prize) = pick_prize(outcome[0]);
+                            let jackpot = GLOBAL_STATE.0.borrow().jackpot;
+                            let (reward, jackpot) = match prize.kind {
+                                RewardKind::Jackpot => {
+                                    let share = jackpot.checked_mul(prize.amount as u64)
+                                        .ok_or(PuppyError::Overflow)? / 100;
+                                    (share, jackpot - share)
+                                },
+                                _ => {
+                                    let jackpot = jackpot.checked_add(get_jackpot_contribution())
+                                        .ok_or(PuppyError::Overflow)?;
+                                    (prize.amount as u64, jackpot)
+                                }
+                            };
+                            pay_prize(player, prize.kind, reward)?;
+                            GLOBAL_STATE.0.borrow_mut().jackpot = jackpot;
+                            insert_event(EVENT_LOTTERY_PRIZE, &mut vec![pid[0], pid[1], tier as u64, prize.kind as u64, reward]);
+                            draw.data = LotteryDraw::default();
+                        } else if player.data.lottery_expired(counter) {
+                            // the blind box was not opened in time, start over
                             player.check_and_inc_nonce(nonce);
-                            player.data.action = 0;
                             player.data.progress = 0;
                             player.data.last_lottery_timestamp = 0;
-                            player.data.last_action_timestamp = 0;
-
-                            // set lottery_info if the last 16 bit are 1
-                            if (rand[1] & 0xff) > 0xf0 {
-                                //zkwasm_rust_sdk::dbg!("rand is {}", {rand[1]});
-                                player.data.lottery_info += 10; // change 10 to random reward
-                            } else {
-                                player.data.balance += 10; // change 10 to random reward
-                            }
                             player.store();
-                            Ok(())
+                            return Err(PuppyError::PlayerLotteryExpired);
+                        } else if player.data.progress == 1000 {
+                            // This is the selected player; allow them to draw a blind box
+                            // against the seed committed for the current epoch
+                            let epoch = GLOBAL_STATE.0.borrow().lottery_epoch;
+                            let committed = SeedCommitment::get_object(epoch)
+                                .map_or(false, |c| !c.data.revealed);
+                            if !committed {
+                                return Err(PuppyError::LotterySeedNotCommitted);
+                            }
+                            draw.data = LotteryDraw { epoch, nonce, rand: *rand };
+                            player.check_and_inc_nonce(nonce);
+                            player.data.action = ACTION_LOTTERY;
+                            player.data.progress = 0;
+                            player.data.last_lottery_timestamp = 0;
+                            player.data.last_action_timestamp = 0;
                         } else {
-                            Err(PLAYER_LOTTERY_PROGRESS_NOT_FULL)
+                            return Err(PuppyError::PlayerLotteryProgressNotFull);
                         }
+                        player.store();
+                        draw.store();
+                        LotteryDraw::emit_event(pid, 0, &draw.data);
+                        Ok(())
                     },
-                    Activity::Comment(_) => {
-                        unreachable!()
+                    Activity::Comment(sz, content) => {
+                        if content.is_empty()
+                            || content.contains(&0)
+                            || content.len() > get_comment_size()
+                            || std::str::from_utf8(content).is_err() {
+                            return Err(PuppyError::InvalidComment);
+                        }
+                        let action = get_action(ACTION_POST_COMMENTS);
+                        player.data.check_and_update_action_timestamp(counter, ACTION_POST_COMMENTS)?;
+                        player.data.cost_ticket(action.cost)?;
+                        player.data.increase_progress(counter, action.reward);
+                        player.check_and_inc_nonce(nonce);
+                        let meme_id = *sz as u64;
+                        let mut meme = MemeInfo::get_object(meme_id).map_or(Err(PuppyError::InvalidMemeIndex), |x| Ok(x))?;
+                        let index = CommentInfo::index(meme_id, meme.data.comments);
+                        let comment = CommentInfo::new_object(CommentInfo {
+                            author: *pid,
+                            meme: meme_id,
+                            timestamp: counter,
+                            content: content.to_vec(),
+                        }, index);
+                        meme.data.comments += 1;
+                        comment.store();
+                        meme.store();
+                        player.store();
+                        CommentInfo::emit_event(index, &comment.data);
+                        MemeInfo::emit_event(meme_id, &meme.data);
+                        Ok(())
                     }
                 }
             }
@@ -190,16 +527,169 @@ impl CommandHandler for Activity {
 }
 
 pub fn decode_error(e: u32) -> &'static str {
-    match e {
-        ERROR_PLAYER_NOT_EXIST => "PlayerNotExist",
-        ERROR_PLAYER_ALREADY_EXIST => "PlayerAlreadyExist",
-        ERROR_NOT_SELECTED_PLAYER => "PlayerNotSelected",
-        SELECTED_PLAYER_NOT_EXIST => "SelectedPlayerNotExist",
-        PLAYER_ACTION_NOT_FINISHED => "PlayerActionNotFinished",
-        PLAYER_LOTTERY_EXPIRED => "PlayerLotteryExpired",
-        PLAYER_LOTTERY_PROGRESS_NOT_FULL => "PlayerLotteryProgressNotFull",
-        PLAYER_NOT_ENOUGH_TICKET => "PlayerNotEnoughTicket",
-        INVALID_MEME_INDEX => "SpecifiedMemeIndexNotFound",
-        _ => "Unknown",
+    PuppyError::try_from(e).map_or("Unknown", |e| e.name())
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_PARAMS;
+    use crate::error::DecodeError;
+    use crate::meme::MAX_MEME_ID;
+    use crate::role::ROLES;
+    use crate::state::Transaction;
+
+    fn decode_err(params: &[u64]) -> Option<DecodeError> {
+        Transaction::try_decode(params).err()
+    }
+
+    fn round_trip(command: Command, nonce: u64) {
+        let transaction = Transaction::decode(&command.encode(nonce));
+        assert_eq!(transaction.command(), &command);
+        assert_eq!(transaction.nonce(), nonce);
+    }
+
+    // xorshift64, deterministic so that failures reproduce
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u64 {
+            self.next() % n
+        }
+
+        fn words<const N: usize>(&mut self) -> [u64; N] {
+            [0; N].map(|_| self.next())
+        }
+
+        fn meme(&mut self) -> usize {
+            self.below(MAX_MEME_ID + 1) as usize
+        }
+
+        // utf8 without zero bytes, the comments the handler accepts
+        fn comment(&mut self) -> Vec<u8> {
+            let len = self.below(40);
+            (0..len)
+                .filter_map(|_| char::from_u32(self.below(0x11000) as u32 + 1))
+                .collect::<String>()
+                .into_bytes()
+        }
+
+        fn command(&mut self) -> Command {
+            match self.below(23) {
+                0 => Command::Activity(Activity::Vote(self.meme())),
+                1 => Command::Activity(Activity::Stake(self.meme(), self.next())),
+                2 => Command::Activity(Activity::Unstake(self.meme(), self.next())),
+                3 => Command::Activity(Activity::Collect(self.meme())),
+                4 => Command::Activity(Activity::Comment(self.meme(), self.comment())),
+                5 => Command::Activity(Activity::Lottery),
+                6 => Command::Activity(Activity::BuyTickets(self.next())),
+                7 => Command::Activity(Activity::CheckIn),
+                8 => Command::Activity(Activity::Act([ACTION_SHAKE_FEET, ACTION_SHAKE_HEAD, ACTION_JUMP][self.below(3) as usize])),
+                9 => Command::Withdraw(Withdraw { data: self.words() }),
+                10 => Command::WithdrawLottery(WithdrawLottery { data: self.words() }),
+                11 => Command::Deposit(Deposit { data: self.words() }),
+                // a zero pid decodes as no referrer
+                12 => Command::InstallPlayer(Some([self.next() | 1, self.next()])),
+                13 => Command::InstallPlayer(None),
+                14 => Command::InstallMeme(self.meme() as u64),
+                15 => Command::Tick,
+                16 => Command::Season([SeasonCommand::Open, SeasonCommand::Close][self.below(2) as usize].clone()),
+                17 => Command::Seed(SeedCommand::Commit(self.words())),
+                18 => Command::Seed(SeedCommand::Reveal(self.words())),
+                19 => Command::UpdateConfig(UpdateConfig {
+                    param: self.below(CONFIG_PARAMS.len() as u64),
+                    value: self.next(),
+                }),
+                20 => Command::Role(RoleCommand::Grant(self.below(ROLES.len() as u64), self.words())),
+                21 => Command::Role(RoleCommand::Revoke(self.below(ROLES.len() as u64), self.words())),
+                _ => Command::SetPause(SetPause { paused: self.next() }),
+            }
+        }
+    }
+
+    #[test]
+    fn generated_commands_round_trip() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..10000 {
+            let command = rng.command();
+            // the nonce takes the 48 bits above the opcode
+            let nonce = rng.below(1 << 48);
+            round_trip(command, nonce);
+        }
+    }
+
+    #[test]
+    fn edge_cases_round_trip() {
+        round_trip(Command::Activity(Activity::Comment(7, vec![])), 0);
+        round_trip(Command::Activity(Activity::Comment(7, b"eight ch".to_vec())), 0);
+        round_trip(Command::Activity(Activity::Comment(MAX_MEME_ID as usize, b"more than eight".to_vec())), (1 << 48) - 1);
+        round_trip(Command::InstallPlayer(Some([0, 1])), 1);
+    }
+
+    #[test]
+    fn unknown_command() {
+        assert_eq!(decode_err(&[0xff]), Some(DecodeError::UnknownCommand(0xff)));
+        assert_eq!(decode_err(&[(3 << 16) + SET_PAUSE + 1]), Some(DecodeError::UnknownCommand(SET_PAUSE + 1)));
+    }
+
+    #[test]
+    fn missing_params() {
+        assert_eq!(decode_err(&[]), Some(DecodeError::MissingParams { command: 0, expected: 1, found: 0 }));
+        let required = [
+            (WITHDRAW, 5), (DEPOSIT, 5), (WITHDRAW_LOTTERY, 5), (COMMIT_SEED, 5), (REVEAL_SEED, 5),
+            (UPDATE_CONFIG, 3), (GRANT_ROLE, 4), (REVOKE_ROLE, 4), (SET_PAUSE, 2), (INSTALL_MEME, 2),
+            (VOTE, 2), (STAKE, 3), (UNSTAKE, 3), (BUY_TICKETS, 2), (COLLECT, 2), (COMMENT, 3),
+        ];
+        for (command, expected) in required {
+            for found in 1..expected {
+                let params = vec![command; found];
+                assert_eq!(
+                    decode_err(&params),
+                    Some(DecodeError::MissingParams { command, expected, found }),
+                    "opcode {}", command
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_meme_index() {
+        let id = MAX_MEME_ID + 1;
+        for params in [
+            vec![VOTE, id],
+            vec![STAKE, id, 1],
+            vec![UNSTAKE, id, 1],
+            vec![COLLECT, id],
+            vec![COMMENT, id, 1],
+            vec![INSTALL_MEME, id],
+        ] {
+            assert_eq!(decode_err(&params), Some(DecodeError::InvalidMemeIndex(id)));
+        }
+        assert!(decode_err(&[VOTE, MAX_MEME_ID]).is_none());
+    }
+
+    #[test]
+    fn invalid_token_index() {
+        assert_eq!(decode_err(&[DEPOSIT, 2, 3, 1, 10]), Some(DecodeError::InvalidTokenIndex(1)));
+    }
+
+    #[test]
+    fn invalid_config_param() {
+        let param = CONFIG_PARAMS.len() as u64;
+        assert_eq!(decode_err(&[UPDATE_CONFIG, param, 1]), Some(DecodeError::InvalidConfigParam(param)));
+    }
+
+    #[test]
+    fn invalid_role() {
+        let role = ROLES.len() as u64;
+        assert_eq!(decode_err(&[GRANT_ROLE, role, 2, 3]), Some(DecodeError::InvalidRole(role)));
+        assert_eq!(decode_err(&[REVOKE_ROLE, role, 2, 3]), Some(DecodeError::InvalidRole(role)));
     }
 }
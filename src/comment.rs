@@ -0,0 +1,54 @@
+use std::slice::IterMut;
+use serde::Serialize;
+use zkwasm_rest_abi::StorageData;
+use crate::runtime::IndexedObject;
+
+#[derive(Clone, Serialize, Default, Debug)]
+pub struct CommentInfo {
+    pub author: [u64; 2],
+    pub meme: u64,
+    pub timestamp: u64,
+    pub content: Vec<u8>, // utf8 text without padding
+}
+
+impl CommentInfo {
+    // comments are numbered per meme; meme ids are expected to fit in 32 bits
+    pub fn index(meme_id: u64, seq: u64) -> u64 {
+        ((meme_id & 0xffffffff) << 32) + seq
+    }
+}
+
+impl StorageData for CommentInfo {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        let author = [*u64data.next().unwrap(), *u64data.next().unwrap()];
+        let meme = *u64data.next().unwrap();
+        let timestamp = *u64data.next().unwrap();
+        let len = *u64data.next().unwrap() as usize;
+        let mut content = u64data.flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+        content.truncate(len);
+        CommentInfo {
+            author,
+            meme,
+            timestamp,
+            content,
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(self.author[0]);
+        data.push(self.author[1]);
+        data.push(self.meme);
+        data.push(self.timestamp);
+        data.push(self.content.len() as u64);
+        for chunk in self.content.chunks(8) {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            data.push(u64::from_le_bytes(bytes));
+        }
+    }
+}
+
+impl IndexedObject<CommentInfo> for CommentInfo {
+    const PREFIX: u64 = 0x1ee3;
+    const POSTFIX: u64 = 0xfee3;
+    const EVENT_NAME: u64 = 0x04;
+}
@@ -1,7 +1,13 @@
+use core::slice::IterMut;
 use serde::Serialize;
+use zkwasm_rest_abi::StorageData;
+use crate::error::PuppyError;
+use crate::runtime::insert_event;
+use crate::state::{COMMANDS, GLOBAL_STATE};
 
 const ACTIONS_SIZE: usize = 5;
 const NAME_SIZE: usize = 5;
+const PRIZE_SIZE: usize = 5;
 
 lazy_static::lazy_static! {
     pub static ref ADMIN_PUBKEY: [u64; 4] = {
@@ -12,27 +18,152 @@ lazy_static::lazy_static! {
     };
 }
 
+#[derive(Serialize, Clone, Copy, Debug)]
+pub enum RewardKind {
+    Balance = 0,
+    LotteryToken = 1,
+    Ticket = 2,
+    Jackpot = 3, // amount is the percentage of the jackpot pool paid out as balance
+}
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct PrizeTier {
+    pub weight: u32,
+    pub kind: RewardKind,
+    pub amount: u32,
+}
+
+// ids of the puppy actions, stored in PlayerData.action while in flight
+pub const ACTION_NONE: u64 = 0;
+pub const ACTION_SHAKE_FEET: u64 = 1;
+pub const ACTION_SHAKE_HEAD: u64 = 2;
+pub const ACTION_JUMP: u64 = 3;
+pub const ACTION_POST_COMMENTS: u64 = 4;
+pub const ACTION_LOTTERY: u64 = 5;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct Action {
+    pub name: &'static str,
+    pub duration: u64, // ticks before the player can start another action
+    pub cost: u64,     // tickets
+    pub reward: u32,   // lottery progress
+}
+
+// Fields that are CONFIG_PARAMS are only the defaults of their runtime value,
+// clients read them from `params` of queryConfig instead.
 #[derive(Serialize, Clone)]
 pub struct Config {
-    actions: [&'static str; ACTIONS_SIZE],
+    actions: [Action; ACTIONS_SIZE], // indexed by action id - 1
     name: [&'static str; NAME_SIZE],
+    #[serde(skip)]
     action_reward: u32,
+    #[serde(skip)]
     action_duration: u64,
+    #[serde(skip)]
+    airdrop_amount: u64,
+    #[serde(skip)]
+    initial_tickets: u64,
+    comment_size: usize,
+    lottery_prizes: [PrizeTier; PRIZE_SIZE],
+    #[serde(skip)]
+    jackpot_contribution: u64,
+    #[serde(skip)]
+    lottery_window: u64,
+    #[serde(skip)]
+    ticket_price: u64,
+    #[serde(skip)]
+    checkin_interval: u64,
+    checkin_reward: u64,
+    checkin_streak_bonus: u64,
+    checkin_max_streak: u64,
+    #[serde(skip)]
+    referral_bonus: u64,
+    #[serde(skip)]
+    referral_share: u64,
 }
 
 lazy_static::lazy_static! {
     pub static ref CONFIG: Config = Config {
-        actions: ["shakeFeet", "shakeHead", "jump", "postComments", "lottery"],
+        actions: [
+            Action { name: "shakeFeet", duration: 2, cost: 1, reward: 50 },
+            Action { name: "shakeHead", duration: 2, cost: 1, reward: 50 },
+            Action { name: "jump", duration: 4, cost: 2, reward: 120 },
+            Action { name: "postComments", duration: 2, cost: 1, reward: 0 },
+            Action { name: "lottery", duration: 0, cost: 0, reward: 0 },
+        ],
         name: ["Bob", "Frank", "Cindy", "Alice", "John"],
         action_reward: 50,
-        action_duration: 2
+        action_duration: 2,
+        airdrop_amount: 50,
+        initial_tickets: 50,
+        comment_size: 128,
+        lottery_prizes: [
+            PrizeTier { weight: 200, kind: RewardKind::Balance, amount: 10 },
+            PrizeTier { weight: 30, kind: RewardKind::Ticket, amount: 5 },
+            PrizeTier { weight: 15, kind: RewardKind::LotteryToken, amount: 10 },
+            PrizeTier { weight: 10, kind: RewardKind::Balance, amount: 50 },
+            PrizeTier { weight: 1, kind: RewardKind::Jackpot, amount: 50 },
+        ],
+        jackpot_contribution: 5,
+        lottery_window: 720,
+        ticket_price: 10,
+        checkin_interval: 17280, // one day of 5 second ticks
+        checkin_reward: 5,
+        checkin_streak_bonus: 1,
+        checkin_max_streak: 7,
+        referral_bonus: 10,
+        referral_share: 10,
     };
 }
 
+#[derive(Serialize)]
+struct Opcode {
+    name: &'static str,
+    opcode: u64,
+}
+
+#[derive(Serialize)]
+struct ErrorCode {
+    code: u32,
+    name: &'static str,
+    message: &'static str,
+}
+
+// what clients learn from queryConfig
+#[derive(Serialize)]
+struct ClientConfig {
+    #[serde(flatten)]
+    config: &'static Config,
+    params: Vec<ParamValue>,
+    commands: Vec<Opcode>,
+    errors: Vec<ErrorCode>,
+    memes: Vec<u64>,
+}
+
+#[derive(Serialize)]
+struct ParamValue {
+    name: &'static str,
+    value: u64,
+    min: u64,
+    max: u64,
+}
+
 impl Config {
     pub fn to_json_string() -> String {
-        let meme_list: Vec<u64> = vec![];
-        serde_json::to_string(&meme_list).unwrap()
+        let commands = COMMANDS.iter().map(|(name, opcode)| Opcode { name, opcode: *opcode }).collect();
+        let errors = PuppyError::ALL.iter().map(|e| ErrorCode {
+            code: e.code(),
+            name: e.name(),
+            message: e.message(),
+        }).collect();
+        let memes = GLOBAL_STATE.0.borrow().memes.clone();
+        let params = CONFIG_PARAMS.iter().enumerate().map(|(id, p)| ParamValue {
+            name: p.name,
+            value: get_param(id as u64),
+            min: p.min,
+            max: p.max,
+        }).collect();
+        serde_json::to_string(&ClientConfig { config: &*CONFIG, params, commands, errors, memes }).unwrap()
     }
 
     // enable timer tick
@@ -41,10 +172,170 @@ impl Config {
     }
 }
 
+// Parameters the admin can change at runtime with UpdateConfig. They start at
+// the value in CONFIG and are overridden by the values kept in GlobalState.
+pub const PARAM_ACTION_REWARD: u64 = 0;
+pub const PARAM_ACTION_DURATION: u64 = 1;
+pub const PARAM_AIRDROP_AMOUNT: u64 = 2;
+pub const PARAM_INITIAL_TICKETS: u64 = 3;
+pub const PARAM_TICKET_PRICE: u64 = 4;
+pub const PARAM_JACKPOT_CONTRIBUTION: u64 = 5;
+pub const PARAM_LOTTERY_WINDOW: u64 = 6;
+pub const PARAM_CHECKIN_INTERVAL: u64 = 7;
+pub const PARAM_REFERRAL_BONUS: u64 = 8;
+pub const PARAM_REFERRAL_SHARE: u64 = 9;
+
+// [param, old value, new value]
+pub const EVENT_CONFIG_UPDATE: u64 = 0x0a;
+
+pub struct ConfigParam {
+    pub name: &'static str,
+    pub min: u64,
+    pub max: u64,
+}
+
+// indexed by param id
+pub const CONFIG_PARAMS: [ConfigParam; 10] = [
+    ConfigParam { name: "action_reward", min: 0, max: 1000 },
+    ConfigParam { name: "action_duration", min: 0, max: 17280 },
+    ConfigParam { name: "airdrop_amount", min: 0, max: 1000 },
+    ConfigParam { name: "initial_tickets", min: 0, max: 1000 },
+    ConfigParam { name: "ticket_price", min: 1, max: 1000000 },
+    ConfigParam { name: "jackpot_contribution", min: 0, max: 1000 },
+    ConfigParam { name: "lottery_window", min: 1, max: 120960 },
+    ConfigParam { name: "checkin_interval", min: 1, max: 120960 },
+    ConfigParam { name: "referral_bonus", min: 0, max: 1000 },
+    ConfigParam { name: "referral_share", min: 0, max: 100 },
+];
+
+fn default_param(param: u64) -> u64 {
+    match param {
+        PARAM_ACTION_REWARD => CONFIG.action_reward as u64,
+        PARAM_ACTION_DURATION => CONFIG.action_duration,
+        PARAM_AIRDROP_AMOUNT => CONFIG.airdrop_amount,
+        PARAM_INITIAL_TICKETS => CONFIG.initial_tickets,
+        PARAM_TICKET_PRICE => CONFIG.ticket_price,
+        PARAM_JACKPOT_CONTRIBUTION => CONFIG.jackpot_contribution,
+        PARAM_LOTTERY_WINDOW => CONFIG.lottery_window,
+        PARAM_CHECKIN_INTERVAL => CONFIG.checkin_interval,
+        PARAM_REFERRAL_BONUS => CONFIG.referral_bonus,
+        PARAM_REFERRAL_SHARE => CONFIG.referral_share,
+        _ => unreachable!(),
+    }
+}
+
+/// Runtime values of the CONFIG_PARAMS, kept in GlobalState and stored with it
+/// as [len, values...]. Params added after the record was written take their
+/// CONFIG value.
+#[derive(Serialize, Clone, Default, Debug)]
+pub struct RuntimeConfig {
+    values: Vec<u64>,
+}
+
+impl StorageData for RuntimeConfig {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        // states written before the params were kept load as all defaults
+        let len = u64data.next().map_or(0, |x| *x);
+        RuntimeConfig {
+            values: (0..len).map(|_| *u64data.next().unwrap()).collect(),
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(self.values.len() as u64);
+        data.extend_from_slice(&self.values);
+    }
+}
+
+impl RuntimeConfig {
+    pub fn get(&self, param: u64) -> u64 {
+        self.values.get(param as usize).copied().unwrap_or_else(|| default_param(param))
+    }
+
+    /// Set a param within its bounds and log the change
+    pub fn update(param: u64, value: u64) -> Result<(), PuppyError> {
+        let bounds = &CONFIG_PARAMS[param as usize];
+        if value < bounds.min || value > bounds.max {
+            return Err(PuppyError::ConfigValueOutOfRange);
+        }
+        let mut state = GLOBAL_STATE.0.borrow_mut();
+        let config = &mut state.params;
+        let old = config.get(param);
+        config.values = (0..CONFIG_PARAMS.len() as u64)
+            .map(|p| if p == param { value } else { config.get(p) })
+            .collect();
+        insert_event(EVENT_CONFIG_UPDATE, &mut vec![param, old, value]);
+        Ok(())
+    }
+}
+
+fn get_param(param: u64) -> u64 {
+    GLOBAL_STATE.0.borrow().params.get(param)
+}
+
 pub fn get_action_duration() -> u64 {
-    CONFIG.action_duration
+    get_param(PARAM_ACTION_DURATION)
 }
 
 pub fn get_action_reward() -> u32 {
-    CONFIG.action_reward
+    get_param(PARAM_ACTION_REWARD) as u32
+}
+
+// balance airdropped to every new player while the airdrop pool lasts
+pub fn get_airdrop_amount() -> u64 {
+    get_param(PARAM_AIRDROP_AMOUNT)
+}
+
+// tickets of a new player
+pub fn get_initial_tickets() -> u64 {
+    get_param(PARAM_INITIAL_TICKETS)
+}
+
+pub fn get_action(id: u64) -> Action {
+    CONFIG.actions[id as usize - 1]
+}
+
+// max bytes of a single comment
+pub fn get_comment_size() -> usize {
+    CONFIG.comment_size
+}
+
+pub fn get_lottery_prizes() -> &'static [PrizeTier] {
+    &CONFIG.lottery_prizes
+}
+
+// added to the jackpot pool by every prize that is not the jackpot
+pub fn get_jackpot_contribution() -> u64 {
+    get_param(PARAM_JACKPOT_CONTRIBUTION)
+}
+
+// ticks a full progress bar can be redeemed for before it expires
+pub fn get_lottery_window() -> u64 {
+    get_param(PARAM_LOTTERY_WINDOW)
+}
+
+// balance paid for a single ticket
+pub fn get_ticket_price() -> u64 {
+    get_param(PARAM_TICKET_PRICE)
+}
+
+// ticks between two check-ins, missing a whole interval resets the streak
+pub fn get_checkin_interval() -> u64 {
+    get_param(PARAM_CHECKIN_INTERVAL)
+}
+
+// tickets of a check-in are checkin_reward + checkin_streak_bonus for every
+// consecutive check-in before it, up to checkin_max_streak in a row
+pub fn get_checkin_reward(streak: u64) -> u64 {
+    let streak = streak.clamp(1, CONFIG.checkin_max_streak);
+    CONFIG.checkin_reward + CONFIG.checkin_streak_bonus * (streak - 1)
+}
+
+// tickets paid to both the referrer and the referee at install
+pub fn get_referral_bonus() -> u64 {
+    get_param(PARAM_REFERRAL_BONUS)
+}
+
+// percentage of the lottery prizes of a referee paid to its referrer instead
+pub fn get_referral_share() -> u64 {
+    get_param(PARAM_REFERRAL_SHARE)
 }
@@ -1,12 +1,209 @@
-pub const ERROR_PLAYER_ALREADY_EXIST: u32 = 1;
-pub const ERROR_PLAYER_NOT_EXIST: u32 = 2;
-pub const ERROR_NOT_SELECTED_PLAYER: u32 = 3;
-pub const SELECTED_PLAYER_NOT_EXIST: u32 = 4;
-pub const PLAYER_ACTION_NOT_FINISHED: u32 = 5;
-pub const PLAYER_LOTTERY_EXPIRED: u32 = 6;
-pub const PLAYER_LOTTERY_PROGRESS_NOT_FULL: u32 = 7;
-pub const PLAYER_NOT_ENOUGH_TICKET: u32 = 8;
-pub const INVALID_MEME_INDEX: u32 = 9;
-pub const NOTHING_TO_COLLECT: u32 = 10;
+/// Errors returned by commands. The numeric codes are part of the rollup
+/// interface (returned as the first word of `Transaction::process`) and must
+/// never be reused or renumbered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PuppyError {
+    PlayerAlreadyExist = 1,
+    PlayerNotExist = 2,
+    NotSelectedPlayer = 3,
+    SelectedPlayerNotExist = 4,
+    PlayerActionNotFinished = 5,
+    PlayerLotteryExpired = 6,
+    PlayerLotteryProgressNotFull = 7,
+    PlayerNotEnoughTicket = 8,
+    InvalidMemeIndex = 9,
+    NothingToCollect = 10,
+    SeasonAlreadyOpen = 11,
+    SeasonNotOpen = 12,
+    PlayerNotEnoughStake = 13,
+    InvalidComment = 14,
+    MemeAlreadyExist = 15,
+    LotterySeedNotCommitted = 16,
+    LotterySeedNotRevealed = 17,
+    LotterySeedAlreadyRevealed = 18,
+    LotterySeedMismatch = 19,
+    Overflow = 20,
+    PlayerNotEnoughBalance = 21,
+    PlayerCheckInNotReady = 22,
+    ReferrerNotExist = 23,
+    ConfigValueOutOfRange = 24,
+    CommandPaused = 25,
+}
 
+impl PuppyError {
+    pub const ALL: [PuppyError; 25] = [
+        PuppyError::PlayerAlreadyExist,
+        PuppyError::PlayerNotExist,
+        PuppyError::NotSelectedPlayer,
+        PuppyError::SelectedPlayerNotExist,
+        PuppyError::PlayerActionNotFinished,
+        PuppyError::PlayerLotteryExpired,
+        PuppyError::PlayerLotteryProgressNotFull,
+        PuppyError::PlayerNotEnoughTicket,
+        PuppyError::InvalidMemeIndex,
+        PuppyError::NothingToCollect,
+        PuppyError::SeasonAlreadyOpen,
+        PuppyError::SeasonNotOpen,
+        PuppyError::PlayerNotEnoughStake,
+        PuppyError::InvalidComment,
+        PuppyError::MemeAlreadyExist,
+        PuppyError::LotterySeedNotCommitted,
+        PuppyError::LotterySeedNotRevealed,
+        PuppyError::LotterySeedAlreadyRevealed,
+        PuppyError::LotterySeedMismatch,
+        PuppyError::Overflow,
+        PuppyError::PlayerNotEnoughBalance,
+        PuppyError::PlayerCheckInNotReady,
+        PuppyError::ReferrerNotExist,
+        PuppyError::ConfigValueOutOfRange,
+        PuppyError::CommandPaused,
+    ];
 
+    pub fn code(&self) -> u32 {
+        *self as u32
+    }
+
+    // machine readable name reported to clients by decode_error
+    pub fn name(&self) -> &'static str {
+        match self {
+            PuppyError::PlayerAlreadyExist => "PlayerAlreadyExist",
+            PuppyError::PlayerNotExist => "PlayerNotExist",
+            PuppyError::NotSelectedPlayer => "PlayerNotSelected",
+            PuppyError::SelectedPlayerNotExist => "SelectedPlayerNotExist",
+            PuppyError::PlayerActionNotFinished => "PlayerActionNotFinished",
+            PuppyError::PlayerLotteryExpired => "PlayerLotteryExpired",
+            PuppyError::PlayerLotteryProgressNotFull => "PlayerLotteryProgressNotFull",
+            PuppyError::PlayerNotEnoughTicket => "PlayerNotEnoughTicket",
+            PuppyError::InvalidMemeIndex => "SpecifiedMemeIndexNotFound",
+            PuppyError::NothingToCollect => "NothingToCollect",
+            PuppyError::SeasonAlreadyOpen => "SeasonAlreadyOpen",
+            PuppyError::SeasonNotOpen => "SeasonNotOpen",
+            PuppyError::PlayerNotEnoughStake => "PlayerNotEnoughStake",
+            PuppyError::InvalidComment => "InvalidComment",
+            PuppyError::MemeAlreadyExist => "MemeAlreadyExist",
+            PuppyError::LotterySeedNotCommitted => "LotterySeedNotCommitted",
+            PuppyError::LotterySeedNotRevealed => "LotterySeedNotRevealed",
+            PuppyError::LotterySeedAlreadyRevealed => "LotterySeedAlreadyRevealed",
+            PuppyError::LotterySeedMismatch => "LotterySeedMismatch",
+            PuppyError::Overflow => "Overflow",
+            PuppyError::PlayerNotEnoughBalance => "PlayerNotEnoughBalance",
+            PuppyError::PlayerCheckInNotReady => "PlayerCheckInNotReady",
+            PuppyError::ReferrerNotExist => "ReferrerNotExist",
+            PuppyError::ConfigValueOutOfRange => "ConfigValueOutOfRange",
+            PuppyError::CommandPaused => "CommandPaused",
+        }
+    }
+
+    pub fn message(&self) -> &'static str {
+        match self {
+            PuppyError::PlayerAlreadyExist => "player already exists",
+            PuppyError::PlayerNotExist => "player does not exist",
+            PuppyError::NotSelectedPlayer => "player is not selected",
+            PuppyError::SelectedPlayerNotExist => "selected player does not exist",
+            PuppyError::PlayerActionNotFinished => "previous action has not finished yet",
+            PuppyError::PlayerLotteryExpired => "lottery redemption window has expired",
+            PuppyError::PlayerLotteryProgressNotFull => "lottery progress is not full",
+            PuppyError::PlayerNotEnoughTicket => "not enough tickets",
+            PuppyError::InvalidMemeIndex => "meme does not exist",
+            PuppyError::NothingToCollect => "no stake position to collect from",
+            PuppyError::SeasonAlreadyOpen => "a season is already open",
+            PuppyError::SeasonNotOpen => "no season is open",
+            PuppyError::PlayerNotEnoughStake => "not enough stake in the position",
+            PuppyError::InvalidComment => "comment is empty, too long, not utf8 or has a zero byte",
+            PuppyError::MemeAlreadyExist => "meme already exists",
+            PuppyError::LotterySeedNotCommitted => "no lottery seed is committed for this epoch",
+            PuppyError::LotterySeedNotRevealed => "lottery seed has not been revealed yet",
+            PuppyError::LotterySeedAlreadyRevealed => "lottery seed is already revealed",
+            PuppyError::LotterySeedMismatch => "revealed seed does not match the commitment",
+            PuppyError::Overflow => "amount overflows",
+            PuppyError::PlayerNotEnoughBalance => "not enough balance",
+            PuppyError::PlayerCheckInNotReady => "already checked in during this interval",
+            PuppyError::ReferrerNotExist => "referrer does not exist",
+            PuppyError::ConfigValueOutOfRange => "config value is out of the allowed range",
+            PuppyError::CommandPaused => "command is paused by the admin",
+        }
+    }
+}
+
+impl From<PuppyError> for u32 {
+    fn from(e: PuppyError) -> u32 {
+        e.code()
+    }
+}
+
+impl TryFrom<u32> for PuppyError {
+    type Error = u32;
+    fn try_from(code: u32) -> Result<Self, u32> {
+        match code {
+            1 => Ok(PuppyError::PlayerAlreadyExist),
+            2 => Ok(PuppyError::PlayerNotExist),
+            3 => Ok(PuppyError::NotSelectedPlayer),
+            4 => Ok(PuppyError::SelectedPlayerNotExist),
+            5 => Ok(PuppyError::PlayerActionNotFinished),
+            6 => Ok(PuppyError::PlayerLotteryExpired),
+            7 => Ok(PuppyError::PlayerLotteryProgressNotFull),
+            8 => Ok(PuppyError::PlayerNotEnoughTicket),
+            9 => Ok(PuppyError::InvalidMemeIndex),
+            10 => Ok(PuppyError::NothingToCollect),
+            11 => Ok(PuppyError::SeasonAlreadyOpen),
+            12 => Ok(PuppyError::SeasonNotOpen),
+            13 => Ok(PuppyError::PlayerNotEnoughStake),
+            14 => Ok(PuppyError::InvalidComment),
+            15 => Ok(PuppyError::MemeAlreadyExist),
+            16 => Ok(PuppyError::LotterySeedNotCommitted),
+            17 => Ok(PuppyError::LotterySeedNotRevealed),
+            18 => Ok(PuppyError::LotterySeedAlreadyRevealed),
+            19 => Ok(PuppyError::LotterySeedMismatch),
+            20 => Ok(PuppyError::Overflow),
+            21 => Ok(PuppyError::PlayerNotEnoughBalance),
+            22 => Ok(PuppyError::PlayerCheckInNotReady),
+            23 => Ok(PuppyError::ReferrerNotExist),
+            24 => Ok(PuppyError::ConfigValueOutOfRange),
+            25 => Ok(PuppyError::CommandPaused),
+            _ => Err(code),
+        }
+    }
+}
+
+/// Reasons `Transaction::try_decode` rejects the params of a transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    UnknownCommand(u64),
+    MissingParams { command: u64, expected: usize, found: usize },
+    InvalidMemeIndex(u64),
+    InvalidTokenIndex(u64),
+    InvalidConfigParam(u64),
+    InvalidRole(u64),
+}
+
+impl DecodeError {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DecodeError::UnknownCommand(_) => "UnknownCommand",
+            DecodeError::MissingParams { .. } => "MissingParams",
+            DecodeError::InvalidMemeIndex(_) => "InvalidMemeIndex",
+            DecodeError::InvalidTokenIndex(_) => "InvalidTokenIndex",
+            DecodeError::InvalidConfigParam(_) => "InvalidConfigParam",
+            DecodeError::InvalidRole(_) => "InvalidRole",
+        }
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+    use crate::command::decode_error;
+
+    #[test]
+    fn every_code_round_trips() {
+        let max = PuppyError::ALL.len() as u32;
+        for code in 1..=max {
+            let e = PuppyError::try_from(code).unwrap();
+            assert_eq!(e.code(), code);
+            assert_eq!(PuppyError::ALL[code as usize - 1], e);
+            assert_eq!(decode_error(code), e.name());
+        }
+        assert_eq!(decode_error(0), "Unknown");
+        assert_eq!(decode_error(max + 1), "Unknown");
+    }
+}
@@ -1,4 +1,6 @@
+#[cfg(not(feature = "native"))]
 use wasm_bindgen::prelude::*;
+#[cfg(not(feature = "native"))]
 use zkwasm_rest_abi::*;
 pub mod config;
 pub mod error;
@@ -7,7 +9,20 @@ pub mod player;
 pub mod settlement;
 pub mod state;
 pub mod meme;
+pub mod season;
+pub mod comment;
+pub mod lottery;
+pub mod referral;
+pub mod role;
+pub mod runtime;
+pub mod storage;
+pub mod supply;
+#[cfg(feature = "native")]
+pub mod native;
 
+#[cfg(not(feature = "native"))]
 use crate::config::Config;
+#[cfg(not(feature = "native"))]
 use crate::state::{GlobalState, Transaction};
+#[cfg(not(feature = "native"))]
 zkwasm_rest_abi::create_zkwasm_apis!(Transaction, GlobalState, Config);
@@ -0,0 +1,137 @@
+use std::slice::IterMut;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use zkwasm_rest_abi::StorageData;
+use crate::runtime::{IndexedObject, Position};
+use crate::config::{get_lottery_prizes, PrizeTier};
+use crate::error::PuppyError;
+
+// [pid_1, pid_2, tier, reward kind, amount before the referrer share]
+pub const EVENT_LOTTERY_PRIZE: u64 = 0x07;
+
+/// Admin commitment to the lottery seed of an epoch.
+/// `hash` is the sha256 of the 32 little endian bytes of `seed`.
+#[derive(Clone, Serialize, Default, Copy, Debug)]
+pub struct SeedCommitment {
+    pub hash: [u64; 4],
+    pub seed: [u64; 4], // all zero until revealed
+    pub revealed: bool,
+}
+
+impl StorageData for SeedCommitment {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        let mut next = || *u64data.next().unwrap();
+        SeedCommitment {
+            hash: [next(), next(), next(), next()],
+            seed: [next(), next(), next(), next()],
+            revealed: next() != 0,
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.extend_from_slice(&self.hash);
+        data.extend_from_slice(&self.seed);
+        data.push(self.revealed as u64);
+    }
+}
+
+impl IndexedObject<SeedCommitment> for SeedCommitment {
+    const PREFIX: u64 = 0x1ee4;
+    const POSTFIX: u64 = 0xfee4;
+    const EVENT_NAME: u64 = 0x05;
+}
+
+/// A lottery ticket waiting for the seed of its epoch to be revealed
+#[derive(Clone, Serialize, Default, Copy, Debug)]
+pub struct LotteryDraw {
+    pub epoch: u64, // 0 if there is no pending draw
+    pub nonce: u64, // player nonce of the drawing transaction
+    pub rand: [u64; 4], // rand of the drawing transaction, unknown when the seed was committed
+}
+
+impl StorageData for LotteryDraw {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        let mut next = || u64data.next().map_or(0, |x| *x);
+        LotteryDraw {
+            epoch: next(),
+            nonce: next(),
+            // draws pending from before the rand was recorded have none
+            rand: [next(), next(), next(), next()],
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(self.epoch);
+        data.push(self.nonce);
+        data.extend_from_slice(&self.rand);
+    }
+}
+
+impl Position<LotteryDraw> for LotteryDraw {
+    const PREFIX: u64 = 0x1ff2;
+    const POSTFIX: u64 = 0xf1f2;
+    const EVENT_NAME: u64 = 0x06;
+}
+
+fn sha256(words: &[u64]) -> [u64; 4] {
+    let mut hasher = Sha256::new();
+    for w in words {
+        hasher.update(w.to_le_bytes());
+    }
+    let digest = hasher.finalize();
+    let mut r = [0u64; 4];
+    for (i, chunk) in digest.chunks(8).enumerate() {
+        r[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    r
+}
+
+impl SeedCommitment {
+    pub fn commit(epoch: u64, hash: [u64; 4]) -> Result<(), PuppyError> {
+        if epoch != 0 {
+            let current = SeedCommitment::get_object(epoch).unwrap();
+            if !current.data.revealed {
+                return Err(PuppyError::LotterySeedNotRevealed);
+            }
+        }
+        let commitment = SeedCommitment::new_object(SeedCommitment { hash, ..SeedCommitment::default() }, epoch + 1);
+        commitment.store();
+        SeedCommitment::emit_event(epoch + 1, &commitment.data);
+        Ok(())
+    }
+
+    pub fn reveal(epoch: u64, seed: [u64; 4]) -> Result<(), PuppyError> {
+        let mut commitment = SeedCommitment::get_object(epoch).map_or(Err(PuppyError::LotterySeedNotCommitted), |x| Ok(x))?;
+        if commitment.data.revealed {
+            Err(PuppyError::LotterySeedAlreadyRevealed)
+        } else if sha256(&seed) != commitment.data.hash {
+            Err(PuppyError::LotterySeedMismatch)
+        } else {
+            commitment.data.seed = seed;
+            commitment.data.revealed = true;
+            commitment.store();
+            SeedCommitment::emit_event(epoch, &commitment.data);
+            Ok(())
+        }
+    }
+}
+
+/// Randomness of a draw: sha256(seed || pid || nonce || rand), verifiable by
+/// anyone once the seed of the epoch is revealed. `rand` is fixed by the drawing
+/// transaction after the seed was committed, so the seed holder can not pick
+/// the nonce that wins a given tier.
+pub fn draw_randomness(seed: &[u64; 4], pid: &[u64; 2], nonce: u64, rand: &[u64; 4]) -> [u64; 4] {
+    sha256(&[seed[0], seed[1], seed[2], seed[3], pid[0], pid[1], nonce, rand[0], rand[1], rand[2], rand[3]])
+}
+
+/// Pick a tier of the prize table; each tier wins with probability weight / total weight.
+pub fn pick_prize(rand: u64) -> (usize, PrizeTier) {
+    let prizes = get_lottery_prizes();
+    let total: u64 = prizes.iter().map(|p| p.weight as u64).sum();
+    let mut r = rand % total;
+    for (tier, prize) in prizes.iter().enumerate() {
+        if r < prize.weight as u64 {
+            return (tier, *prize);
+        }
+        r -= prize.weight as u64;
+    }
+    unreachable!()
+}
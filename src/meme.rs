@@ -1,31 +1,58 @@
 use std::slice::IterMut;
 use serde::Serialize;
 use zkwasm_rest_abi::StorageData;
-use zkwasm_rest_convention::{IndexedObject, Position};
+use crate::runtime::{require, IndexedObject, Position};
+use crate::storage::{layout_tag, read_layout};
+
+// version 1: tagged, comments counter always present
+// version 2: version 1 followed by season_rank
+const MEME_LAYOUT: u64 = 2;
+const STAKE_LAYOUT: u64 = 1;
+// version 0 memes have [id, rank, stake, owner_1, owner_2] and maybe comments
+const MEME_UNTAGGED_LEN: usize = 6;
+// version 0 stakes have [stake, timestamp]
+const STAKE_UNTAGGED_LEN: usize = 2;
+
+// meme ids are packed into 32 bits in season and comment indexes
+pub const MAX_MEME_ID: u64 = 0xffffffff;
+
+// words written by to_data, in order
+pub const MEME_INFO_FIELDS: [&str; 8] = ["layout", "id", "rank", "stake", "owner_1", "owner_2", "comments", "season_rank"];
+pub const STAKE_INFO_FIELDS: [&str; 3] = ["layout", "stake", "timestamp"];
 
 #[derive(Clone, Serialize, Default, Copy)]
 pub struct MemeInfo {
     pub id: u64,
-    pub rank: u64,
+    pub rank: u64, // votes of all seasons, staking yield is paid against it
     pub stake: u64,
     pub owner: [u64; 2],
+    pub comments: u64, // number of comments posted on this meme
+    pub season_rank: u64, // votes of the open season, archived and reset on rollover
 }
 
 impl StorageData for MemeInfo {
     fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        let (version, mut fields) = read_layout(u64data, MEME_UNTAGGED_LEN);
+        unsafe { require(version <= MEME_LAYOUT) };
         MemeInfo {
-            id: *u64data.next().unwrap(),
-            rank: *u64data.next().unwrap(),
-            stake: *u64data.next().unwrap(),
-            owner: [*u64data.next().unwrap(),*u64data.next().unwrap()],
+            id: fields.next().unwrap(),
+            rank: fields.next().unwrap(),
+            stake: fields.next().unwrap(),
+            owner: [fields.next().unwrap(), fields.next().unwrap()],
+            // version 0 memes installed before comments existed have no counter
+            comments: fields.next().unwrap_or(0),
+            season_rank: fields.next().unwrap_or(0),
         }
     }
     fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(layout_tag(MEME_LAYOUT));
         data.push(self.id);
         data.push(self.rank);
         data.push(self.stake);
         data.push(self.owner[0]);
         data.push(self.owner[1]);
+        data.push(self.comments);
+        data.push(self.season_rank);
     }
 }
 
@@ -37,12 +64,16 @@ pub struct StakeInfo {
 
 impl StorageData for StakeInfo {
     fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        // version 0 and 1 share the same fields
+        let (version, mut fields) = read_layout(u64data, STAKE_UNTAGGED_LEN);
+        unsafe { require(version <= STAKE_LAYOUT) };
         StakeInfo {
-            stake: *u64data.next().unwrap(),
-            timestamp: *u64data.next().unwrap(),
+            stake: fields.next().unwrap(),
+            timestamp: fields.next().unwrap(),
         }
     }
     fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(layout_tag(STAKE_LAYOUT));
         data.push(self.stake);
         data.push(self.timestamp);
     }
@@ -60,3 +91,79 @@ impl Position<StakeInfo> for StakeInfo {
     const POSTFIX: u64 = 0xf1f1;
     const EVENT_NAME: u64 = 0x01;
 }
+
+/// Players with a non zero stake on a meme, indexed by meme id. Positions
+/// opened before the index existed are added the next time they are touched.
+#[derive(Clone, Serialize, Default, Debug)]
+pub struct MemeStakers {
+    pub stakers: Vec<[u64; 2]>,
+}
+
+impl StorageData for MemeStakers {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        let len = *u64data.next().unwrap();
+        MemeStakers {
+            stakers: (0..len).map(|_| [*u64data.next().unwrap(), *u64data.next().unwrap()]).collect(),
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(self.stakers.len() as u64);
+        for pid in self.stakers.iter() {
+            data.extend_from_slice(pid);
+        }
+    }
+}
+
+impl IndexedObject<MemeStakers> for MemeStakers {
+    const PREFIX: u64 = 0x1ee5;
+    const POSTFIX: u64 = 0xfee5;
+    const EVENT_NAME: u64 = 0x0c; // never emitted
+}
+
+impl MemeStakers {
+    /// Add or drop `pid` from the stakers of a meme after its position changed
+    pub fn track(meme_index: u64, pid: &[u64; 2], stake: u64) {
+        let mut stakers = MemeStakers::get_object(meme_index)
+            .unwrap_or_else(|| MemeStakers::new_object(MemeStakers::default(), meme_index));
+        let listed = stakers.data.stakers.contains(pid);
+        if stake > 0 && !listed {
+            stakers.data.stakers.push(*pid);
+            stakers.store();
+        } else if stake == 0 && listed {
+            stakers.data.stakers.retain(|p| p != pid);
+            stakers.store();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn untagged_meme_without_comments() {
+        let mut data = vec![7, 3, 100, 2, 3];
+        let meme = MemeInfo::from_data(&mut data.iter_mut());
+        assert_eq!((meme.id, meme.rank, meme.stake, meme.owner, meme.comments), (7, 3, 100, [2, 3], 0));
+        let mut stored = vec![];
+        meme.to_data(&mut stored);
+        assert_eq!(stored, vec![layout_tag(MEME_LAYOUT), 7, 3, 100, 2, 3, 0, 0]);
+    }
+
+    #[test]
+    fn untagged_meme_with_comments() {
+        let mut data = vec![7, 3, 100, 2, 3, 4];
+        let meme = MemeInfo::from_data(&mut data.iter_mut());
+        assert_eq!(meme.comments, 4);
+    }
+
+    #[test]
+    fn untagged_stake() {
+        let mut data = vec![100, 20];
+        let stake = StakeInfo::from_data(&mut data.iter_mut());
+        assert_eq!((stake.stake, stake.timestamp), (100, 20));
+        let mut stored = vec![];
+        stake.to_data(&mut stored);
+        assert_eq!(stored, vec![layout_tag(STAKE_LAYOUT), 100, 20]);
+    }
+}
@@ -0,0 +1,293 @@
+//! In-memory replacements for the zkWasm host bindings.
+//!
+//! Enabled with the `native` feature so that `Transaction::decode` and
+//! `Transaction::process` can run as a plain host program (e.g. under
+//! `cargo test --features native`). Every item mirrors the signature of the
+//! zkwasm-rest-abi / zkwasm-rest-convention item it replaces, see `runtime.rs`.
+//!
+//! The backing store is a single global map, so scenarios sharing the process
+//! must call `reset` first and run with `--test-threads=1`.
+//!
+//! In debug builds `run_transaction` also checks that the assets held by all
+//! players in the store moved by exactly the minted and burned supply.
+
+use crate::player::PuppyPlayer;
+use crate::settlement::SettlementInfo;
+use crate::state::{GlobalState, Transaction, GLOBAL_STATE};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use zkwasm_rest_abi::StorageData;
+
+pub struct MemoryMap(BTreeMap<[u64; 4], Vec<u64>>);
+
+impl MemoryMap {
+    pub const fn new() -> Self {
+        MemoryMap(BTreeMap::new())
+    }
+
+    pub fn get(&self, key: &[u64; 4]) -> Vec<u64> {
+        self.0.get(key).cloned().unwrap_or_default()
+    }
+
+    pub fn set(&mut self, key: &[u64; 4], data: &[u64]) {
+        self.0.insert(*key, data.to_vec());
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&[u64; 4], &Vec<u64>)> {
+        self.0.iter()
+    }
+}
+
+pub static mut MERKLE_MAP: MemoryMap = MemoryMap::new();
+static mut EVENTS: Vec<u64> = vec![];
+
+pub unsafe fn require(cond: bool) {
+    assert!(cond, "require failed");
+}
+
+pub fn enforce(cond: bool, msg: &str) {
+    assert!(cond, "{}", msg);
+}
+
+pub fn insert_event(typ: u64, data: &mut Vec<u64>) {
+    let events = unsafe { &mut EVENTS };
+    events.push((typ << 32) + data.len() as u64);
+    events.append(data);
+}
+
+pub fn clear_events(a: Vec<u64>) -> Vec<u64> {
+    let events = unsafe { &mut EVENTS };
+    let mut r = a;
+    r.append(events);
+    r
+}
+
+#[derive(Debug, Serialize)]
+pub struct Player<T: StorageData + Default> {
+    pub player_id: [u64; 2],
+    pub nonce: u64,
+    pub data: T,
+}
+
+impl<T: StorageData + Default> Player<T> {
+    pub fn to_key(pid: &[u64; 2]) -> [u64; 4] {
+        [pid[0], pid[1], 0xff01, 0xff02]
+    }
+
+    pub fn pkey_to_pid(pkey: &[u64; 4]) -> [u64; 2] {
+        [pkey[1], pkey[2]]
+    }
+
+    pub fn new_from_pid(pid: [u64; 2]) -> Self {
+        Player {
+            player_id: pid,
+            nonce: 0,
+            data: T::default(),
+        }
+    }
+
+    pub fn get_from_pid(pid: &[u64; 2]) -> Option<Self> {
+        let kvpair = unsafe { &mut MERKLE_MAP };
+        let mut data = kvpair.get(&Self::to_key(pid));
+        if data.is_empty() {
+            None
+        } else {
+            let mut u64data = data.iter_mut();
+            let nonce = *u64data.next().unwrap();
+            Some(Player {
+                player_id: *pid,
+                nonce,
+                data: T::from_data(&mut u64data),
+            })
+        }
+    }
+
+    pub fn check_and_inc_nonce(&mut self, nonce: u64) {
+        assert!(self.nonce == nonce, "nonce mismatch");
+        self.nonce += 1;
+    }
+
+    pub fn store(&self) {
+        let mut data = vec![self.nonce];
+        self.data.to_data(&mut data);
+        let kvpair = unsafe { &mut MERKLE_MAP };
+        kvpair.set(&Self::to_key(&self.player_id), data.as_slice());
+    }
+}
+
+pub struct Wrapped<T: StorageData> {
+    key: [u64; 4],
+    pub data: T,
+}
+
+impl<T: StorageData> Wrapped<T> {
+    pub fn store(&self) {
+        let mut data = vec![];
+        self.data.to_data(&mut data);
+        let kvpair = unsafe { &mut MERKLE_MAP };
+        kvpair.set(&self.key, data.as_slice());
+    }
+}
+
+fn load<T: StorageData>(key: [u64; 4]) -> Option<Wrapped<T>> {
+    let kvpair = unsafe { &mut MERKLE_MAP };
+    let mut data = kvpair.get(&key);
+    if data.is_empty() {
+        None
+    } else {
+        Some(Wrapped {
+            key,
+            data: T::from_data(&mut data.iter_mut()),
+        })
+    }
+}
+
+pub trait IndexedObject<T: StorageData> {
+    const PREFIX: u64;
+    const POSTFIX: u64;
+    const EVENT_NAME: u64;
+
+    fn to_key(index: u64) -> [u64; 4] {
+        [Self::PREFIX, index, 0, Self::POSTFIX]
+    }
+
+    fn new_object(data: T, index: u64) -> Wrapped<T> {
+        Wrapped {
+            key: Self::to_key(index),
+            data,
+        }
+    }
+
+    fn get_object(index: u64) -> Option<Wrapped<T>> {
+        load(Self::to_key(index))
+    }
+
+    fn emit_event(index: u64, data: &T) {
+        let mut v = vec![index];
+        data.to_data(&mut v);
+        insert_event(Self::EVENT_NAME, &mut v);
+    }
+}
+
+pub trait Position<T: StorageData> {
+    const PREFIX: u64;
+    const POSTFIX: u64;
+    const EVENT_NAME: u64;
+
+    fn to_key(pid: &[u64; 2], index: u64) -> [u64; 4] {
+        [Self::PREFIX, pid[0], pid[1], (index << 16) + Self::POSTFIX]
+    }
+
+    fn get_position(pid: &[u64; 2], index: u64) -> Option<Wrapped<T>> {
+        load(Self::to_key(pid, index))
+    }
+
+    fn get_or_new_position(pid: &[u64; 2], index: u64, default: T) -> Wrapped<T> {
+        Self::get_position(pid, index).unwrap_or(Wrapped {
+            key: Self::to_key(pid, index),
+            data: default,
+        })
+    }
+
+    fn emit_event(pid: &[u64; 2], index: u64, data: &T) {
+        let mut v = vec![pid[0], pid[1], index];
+        data.to_data(&mut v);
+        insert_event(Self::EVENT_NAME, &mut v);
+    }
+}
+
+/// Drop all stored objects, pending events and settlements and restore the
+/// initial `GlobalState`.
+pub fn reset() {
+    unsafe {
+        MERKLE_MAP = MemoryMap::new();
+        EVENTS = vec![];
+    }
+    SettlementInfo::flush_settlement();
+    *GLOBAL_STATE.0.borrow_mut() = GlobalState::new();
+    GlobalState::initialize();
+}
+
+/// Load a key-value dump into the store and fetch the global state from it
+pub fn load_kvpairs(kvpairs: Vec<([u64; 4], Vec<u64>)>) {
+    reset();
+    let kvpair = unsafe { &mut MERKLE_MAP };
+    for (key, data) in kvpairs {
+        kvpair.set(&key, &data);
+    }
+    GlobalState::initialize();
+}
+
+/// Copy of the store and the pending events taken before a transaction that
+/// may trap. Pending settlements are not part of it.
+pub struct Checkpoint {
+    map: BTreeMap<[u64; 4], Vec<u64>>,
+    events: Vec<u64>,
+}
+
+/// Persist the global state and copy the store
+pub fn checkpoint() -> Checkpoint {
+    GlobalState::store();
+    let kvpair = unsafe { &mut MERKLE_MAP };
+    let events = unsafe { &mut EVENTS };
+    Checkpoint {
+        map: kvpair.0.clone(),
+        events: events.clone(),
+    }
+}
+
+/// Roll the store back to a checkpoint and fetch the global state from it
+pub fn rollback(checkpoint: Checkpoint) {
+    unsafe {
+        MERKLE_MAP = MemoryMap(checkpoint.map);
+        EVENTS = checkpoint.events;
+    }
+    *GLOBAL_STATE.0.borrow_mut() = GlobalState::new();
+    GlobalState::initialize();
+}
+
+/// Tickets, balance and lottery tokens held by all players in the store,
+/// indexed by `supply::Asset`
+pub fn holdings() -> [u64; 3] {
+    let kvpair = unsafe { &mut MERKLE_MAP };
+    let pids = kvpair
+        .iter()
+        .filter(|(key, _)| key[2] == 0xff01 && key[3] == 0xff02)
+        .map(|(key, _)| [key[0], key[1]])
+        .collect::<Vec<_>>();
+    let mut sum = [0u64; 3];
+    for pid in pids {
+        let player = PuppyPlayer::get_from_pid(&pid).unwrap();
+        sum[0] = sum[0].wrapping_add(player.data.ticket);
+        sum[1] = sum[1].wrapping_add(player.data.balance);
+        sum[2] = sum[2].wrapping_add(player.data.lottery_info);
+    }
+    sum
+}
+
+/// Run a single transaction the way the rollup does: decode the params,
+/// process them with the signer's public key and persist the global state.
+/// Returns the error code followed by the emitted events.
+pub fn run_transaction(pkey: &[u64; 4], params: &[u64], rand: &[u64; 4]) -> Vec<u64> {
+    // compare deltas so that dumps recorded before supply tracking still replay
+    let before = cfg!(debug_assertions)
+        .then(|| (holdings(), GLOBAL_STATE.0.borrow().supply.circulating()));
+    let transaction = Transaction::decode(params);
+    let r = transaction.process(pkey, rand);
+    GlobalState::store();
+    if let Some((held, circulating)) = before {
+        let held_now = holdings();
+        let circulating_now = GLOBAL_STATE.0.borrow().supply.circulating();
+        for asset in 0..3 {
+            assert_eq!(
+                held_now[asset].wrapping_sub(held[asset]),
+                circulating_now[asset].wrapping_sub(circulating[asset]),
+                "supply drift of asset {} after {:?}",
+                asset,
+                params
+            );
+        }
+    }
+    r
+}
@@ -1,23 +1,42 @@
 use crate::meme::MemeInfo;
 use crate::meme::StakeInfo;
-use crate::Player;
-use crate::StorageData;
+use crate::meme::MemeStakers;
+use crate::runtime::Player;
 use core::slice::IterMut;
 use serde::Serialize;
-use zkwasm_rest_convention::IndexedObject;
-use zkwasm_rest_convention::Position;
-use zkwasm_rest_convention::Wrapped;
-use crate::error::*;
+use zkwasm_rest_abi::StorageData;
+use crate::runtime::IndexedObject;
+use crate::runtime::Position;
+use crate::runtime::Wrapped;
+use crate::error::PuppyError;
+use crate::config::{
+    get_action, get_action_duration, get_checkin_interval, get_checkin_reward, get_initial_tickets, get_lottery_window,
+    RewardKind, ACTION_NONE,
+};
+use crate::runtime::require;
+use crate::storage::{layout_tag, read_layout};
+use crate::supply::{burn, mint, Asset};
+
+// version 0: untagged [balance << 32 | ticket, progress << 32 | lottery_info, action, last_lottery_timestamp, last_action_timestamp]
+// version 1: tagged, same fields as version 0
+// version 2: tagged [balance, ticket, lottery_info, progress, action, last_lottery_timestamp, last_action_timestamp]
+// version 3: version 2 followed by [last_checkin_timestamp, checkin_streak]
+// version 4: version 3 followed by [referrer_1, referrer_2]
+const PLAYER_LAYOUT: u64 = 4;
+const PLAYER_UNTAGGED_LEN: usize = 5;
 
 #[derive(Clone, Serialize, Debug)]
 pub struct PlayerData {
-    pub balance: u32,
-    pub ticket: u32,
-    pub action: u64,
+    pub balance: u64,
+    pub ticket: u64,
+    pub action: u64, // id of the action in flight, ACTION_NONE after a vote
     pub last_lottery_timestamp: u64, // last timestamp when this user allowed to pick a lottery
     pub last_action_timestamp: u64,  // last timestamp when this user allowed to pick a lottery
-    pub lottery_info: u32,
+    pub lottery_info: u64,
     pub progress: u32,
+    pub last_checkin_timestamp: u64,
+    pub checkin_streak: u64, // consecutive check-ins, 0 if the player never checked in
+    pub referrer: [u64; 2],  // [0, 0] if the player was not referred
 }
 
 impl Default for PlayerData {
@@ -27,37 +46,71 @@ impl Default for PlayerData {
             last_lottery_timestamp: 0,
             last_action_timestamp: 0, // last timestamp when this user allowed to pick a lottery
             balance: 0,
-            ticket: 50,
+            ticket: get_initial_tickets(),
             lottery_info: 0,
             progress: 0,
+            last_checkin_timestamp: 0,
+            checkin_streak: 0,
+            referrer: [0, 0],
         }
     }
 }
 
 impl StorageData for PlayerData {
     fn from_data(u64data: &mut IterMut<u64>) -> Self {
-        let bt = *u64data.next().unwrap();
-        let balance = (bt >> 32) as u32;
-        let ticket = (bt & 0xffffffff) as u32;
-        let lot = *u64data.next().unwrap();
-        let progress = (lot >> 32) as u32;
-        let lottery_info = (lot & 0xffffffff) as u32;
-        PlayerData {
-            progress,
-            lottery_info,
-            balance,
-            ticket,
-            action: *u64data.next().unwrap(),
-            last_lottery_timestamp: *u64data.next().unwrap(),
-            last_action_timestamp: *u64data.next().unwrap(),
+        let (version, mut fields) = read_layout(u64data, PLAYER_UNTAGGED_LEN);
+        match version {
+            0 | 1 => {
+                let bt = fields.next().unwrap();
+                let balance = bt >> 32;
+                let ticket = bt & 0xffffffff;
+                let lot = fields.next().unwrap();
+                let progress = (lot >> 32) as u32;
+                let lottery_info = lot & 0xffffffff;
+                PlayerData {
+                    progress,
+                    lottery_info,
+                    balance,
+                    ticket,
+                    action: fields.next().unwrap(),
+                    last_lottery_timestamp: fields.next().unwrap(),
+                    last_action_timestamp: fields.next().unwrap(),
+                    last_checkin_timestamp: 0,
+                    checkin_streak: 0,
+                    referrer: [0, 0],
+                }
+            },
+            2..=4 => PlayerData {
+                balance: fields.next().unwrap(),
+                ticket: fields.next().unwrap(),
+                lottery_info: fields.next().unwrap(),
+                progress: fields.next().unwrap() as u32,
+                action: fields.next().unwrap(),
+                last_lottery_timestamp: fields.next().unwrap(),
+                last_action_timestamp: fields.next().unwrap(),
+                last_checkin_timestamp: fields.next().unwrap_or(0),
+                checkin_streak: fields.next().unwrap_or(0),
+                referrer: [fields.next().unwrap_or(0), fields.next().unwrap_or(0)],
+            },
+            _ => {
+                unsafe { require(false) };
+                unreachable!()
+            }
         }
     }
     fn to_data(&self, data: &mut Vec<u64>) {
-        data.push(((self.balance as u64) << 32) + (self.ticket as u64));
-        data.push(((self.progress as u64) << 32) + (self.lottery_info as u64));
+        data.push(layout_tag(PLAYER_LAYOUT));
+        data.push(self.balance);
+        data.push(self.ticket);
+        data.push(self.lottery_info);
+        data.push(self.progress as u64);
         data.push(self.action);
         data.push(self.last_lottery_timestamp);
         data.push(self.last_action_timestamp);
+        data.push(self.last_checkin_timestamp);
+        data.push(self.checkin_streak);
+        data.push(self.referrer[0]);
+        data.push(self.referrer[1]);
     }
 }
 
@@ -79,51 +132,122 @@ impl Owner for PuppyPlayer {
 }
 
 impl PlayerData {
-    pub fn check_and_update_action_timestamp(&mut self, counter: u64, duration: u64) -> Result<(), u32> {
+    // start `action` once the action in flight has finished
+    pub fn check_and_update_action_timestamp(&mut self, counter: u64, action: u64) -> Result<(), PuppyError> {
+        let duration = if self.action == ACTION_NONE {
+            get_action_duration()
+        } else {
+            get_action(self.action).duration
+        };
         if self.last_action_timestamp != 0
             && counter < self.last_action_timestamp + duration
             {
-                Err(PLAYER_ACTION_NOT_FINISHED)
+                Err(PuppyError::PlayerActionNotFinished)
             } else {
                 self.last_action_timestamp = counter;
+                self.action = action;
                 Ok(())
             }
     }
+    /// Grant the check-in tickets if a full interval passed since the last
+    /// check-in, returns the number of tickets granted
+    pub fn check_in(&mut self, counter: u64) -> Result<u64, PuppyError> {
+        let interval = get_checkin_interval();
+        if self.checkin_streak != 0 && counter < self.last_checkin_timestamp + interval {
+            return Err(PuppyError::PlayerCheckInNotReady);
+        }
+        let streak = if self.checkin_streak != 0 && counter < self.last_checkin_timestamp + 2 * interval {
+            self.checkin_streak + 1
+        } else {
+            1
+        };
+        let reward = get_checkin_reward(streak);
+        self.add_ticket(reward)?;
+        self.checkin_streak = streak;
+        self.last_checkin_timestamp = counter;
+        Ok(reward)
+    }
     pub fn increase_progress(&mut self, counter:u64, progress: u32) {
+        let full = self.progress == 1000;
         self.progress += progress;
         if self.progress >= 1000 {
             self.progress = 1000;
         }
-        if self.progress == 1000 {
+        // the redemption window starts when the bar fills up
+        if self.progress == 1000 && !full {
             self.last_lottery_timestamp = counter;
         }
 
     }
-    pub fn cost_ticket(&mut self, amount: u32) -> Result<(), u32> {
-        if self.ticket < amount {
-            Err(PLAYER_NOT_ENOUGH_TICKET)
+    pub fn lottery_expired(&self, counter: u64) -> bool {
+        self.progress == 1000 && counter >= self.last_lottery_timestamp + get_lottery_window()
+    }
+    // ticks left to redeem a full progress bar, 0 if the bar is not redeemable
+    pub fn lottery_remaining(&self, counter: u64) -> u64 {
+        if self.progress == 1000 {
+            (self.last_lottery_timestamp + get_lottery_window()).saturating_sub(counter)
         } else {
-            self.ticket -= amount;
-            Ok(())
+            0
+        }
+    }
+    // the helpers below are the only places assets enter or leave a player,
+    // they keep the supply totals of GlobalState in sync
+    pub fn cost_ticket(&mut self, amount: u64) -> Result<(), PuppyError> {
+        self.ticket = self.ticket.checked_sub(amount).ok_or(PuppyError::PlayerNotEnoughTicket)?;
+        burn(Asset::Ticket, amount);
+        Ok(())
+    }
+    pub fn cost_balance(&mut self, amount: u64) -> Result<(), PuppyError> {
+        self.balance = self.balance.checked_sub(amount).ok_or(PuppyError::PlayerNotEnoughBalance)?;
+        burn(Asset::Balance, amount);
+        Ok(())
+    }
+    pub fn cost_lottery_info(&mut self, amount: u64) -> Result<(), PuppyError> {
+        self.lottery_info = self.lottery_info.checked_sub(amount).ok_or(PuppyError::PlayerNotEnoughBalance)?;
+        burn(Asset::LotteryToken, amount);
+        Ok(())
+    }
+    pub fn add_ticket(&mut self, amount: u64) -> Result<(), PuppyError> {
+        self.ticket = self.ticket.checked_add(amount).ok_or(PuppyError::Overflow)?;
+        mint(Asset::Ticket, amount);
+        Ok(())
+    }
+    pub fn add_balance(&mut self, amount: u64) -> Result<(), PuppyError> {
+        self.balance = self.balance.checked_add(amount).ok_or(PuppyError::Overflow)?;
+        mint(Asset::Balance, amount);
+        Ok(())
+    }
+    pub fn add_lottery_info(&mut self, amount: u64) -> Result<(), PuppyError> {
+        self.lottery_info = self.lottery_info.checked_add(amount).ok_or(PuppyError::Overflow)?;
+        mint(Asset::LotteryToken, amount);
+        Ok(())
+    }
+    // jackpot prizes are paid out as balance
+    pub fn add_reward(&mut self, kind: RewardKind, amount: u64) -> Result<(), PuppyError> {
+        match kind {
+            RewardKind::Balance | RewardKind::Jackpot => self.add_balance(amount),
+            RewardKind::LotteryToken => self.add_lottery_info(amount),
+            RewardKind::Ticket => self.add_ticket(amount),
         }
     }
 }
 
 pub trait PositionHolder: Sized {
-    fn stake(&mut self, meme_index: u64, amount: u32, timestampe: u64) -> Result<(Wrapped<StakeInfo>, Wrapped<MemeInfo>), u32>;
-    fn collect(&mut self, meme_index: u64, timestamp: u64) -> Result<Wrapped<StakeInfo>, u32>;
+    fn stake(&mut self, meme_index: u64, amount: u64, timestampe: u64) -> Result<(Wrapped<StakeInfo>, Wrapped<MemeInfo>), PuppyError>;
+    fn collect(&mut self, meme_index: u64, timestamp: u64) -> Result<Wrapped<StakeInfo>, PuppyError>;
+    fn unstake(&mut self, meme_index: u64, amount: u64, timestamp: u64) -> Result<(Wrapped<StakeInfo>, Wrapped<MemeInfo>), PuppyError>;
 }
 
 
 
 impl PositionHolder for Player<PlayerData> {
-    fn stake(&mut self, meme_index: u64, amount: u32, timestamp: u64) -> Result<(Wrapped<StakeInfo>, Wrapped<MemeInfo>), u32> {
+    fn stake(&mut self, meme_index: u64, amount: u64, timestamp: u64) -> Result<(Wrapped<StakeInfo>, Wrapped<MemeInfo>), PuppyError> {
         self.data.cost_ticket(amount)?;
         let mut pos = StakeInfo::get_or_new_position(&self.player_id, meme_index, StakeInfo { stake: 0, timestamp});
         let meme = MemeInfo::get_object(meme_index);
         match meme {
             Some (mut m) => {
-                pos.data.stake += amount as u64;
+                pos.data.stake = pos.data.stake.checked_add(amount).ok_or(PuppyError::Overflow)?;
                 pos.data.timestamp = timestamp;
                 if m.data.stake < pos.data.stake {
                     m.data.stake = pos.data.stake;
@@ -131,16 +255,95 @@ impl PositionHolder for Player<PlayerData> {
                 }
                 Ok((pos, m))
             }
-            None => Err(INVALID_MEME_INDEX)
+            None => Err(PuppyError::InvalidMemeIndex)
         }
     }
-    fn collect(&mut self, meme_index: u64, timestamp: u64) -> Result<Wrapped<StakeInfo>, u32> {
-        let mut pos = StakeInfo::get_position(&self.player_id, meme_index).map_or(Err(NOTHING_TO_COLLECT), |x| Ok(x))?;
-        let meme = MemeInfo::get_object(meme_index).map_or(Err(INVALID_MEME_INDEX), |x| Ok(x))?;
+    fn collect(&mut self, meme_index: u64, timestamp: u64) -> Result<Wrapped<StakeInfo>, PuppyError> {
+        let mut pos = StakeInfo::get_position(&self.player_id, meme_index).map_or(Err(PuppyError::NothingToCollect), |x| Ok(x))?;
+        let meme = MemeInfo::get_object(meme_index).map_or(Err(PuppyError::InvalidMemeIndex), |x| Ok(x))?;
         let delta = timestamp - pos.data.timestamp;
-        let collectable = delta * pos.data.stake * meme.data.rank / 10000000;
+        // widen so that a large product still collects as long as the yield fits in u64
+        let collectable = (delta as u128 * pos.data.stake as u128)
+            .checked_mul(meme.data.rank as u128)
+            .map(|x| x / 10000000)
+            .and_then(|x| u64::try_from(x).ok())
+            .ok_or(PuppyError::Overflow)?;
         pos.data.timestamp = timestamp;
-        self.data.balance += collectable as u32;
+        self.data.add_balance(collectable)?;
         Ok(pos)
     }
+    fn unstake(&mut self, meme_index: u64, amount: u64, timestamp: u64) -> Result<(Wrapped<StakeInfo>, Wrapped<MemeInfo>), PuppyError> {
+        // settle the yield of the full position before it shrinks
+        let mut pos = self.collect(meme_index, timestamp)?;
+        let mut meme = MemeInfo::get_object(meme_index).map_or(Err(PuppyError::InvalidMemeIndex), |x| Ok(x))?;
+        pos.data.stake = pos.data.stake.checked_sub(amount).ok_or(PuppyError::PlayerNotEnoughStake)?;
+        self.data.add_ticket(amount)?;
+        if meme.data.owner == self.player_id {
+            // hand the meme to the largest remaining position, the owner keeps it on a tie
+            let mut owner = if pos.data.stake > 0 { self.player_id } else { [0, 0] };
+            let mut stake = pos.data.stake;
+            let stakers = MemeStakers::get_object(meme_index).map_or(vec![], |s| s.data.stakers);
+            for pid in stakers.iter().filter(|pid| **pid != self.player_id) {
+                let other = StakeInfo::get_position(pid, meme_index).map_or(0, |p| p.data.stake);
+                if other > stake {
+                    owner = *pid;
+                    stake = other;
+                }
+            }
+            meme.data.owner = owner;
+            meme.data.stake = stake;
+        }
+        Ok((pos, meme))
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    fn load(mut data: Vec<u64>) -> PlayerData {
+        PlayerData::from_data(&mut data.iter_mut())
+    }
+
+    fn stored(player: &PlayerData) -> Vec<u64> {
+        let mut data = vec![];
+        player.to_data(&mut data);
+        data
+    }
+
+    #[test]
+    fn untagged_v0_player() {
+        let player = load(vec![(30 << 32) | 40, (500 << 32) | 6, ACTION_NONE, 7, 8]);
+        assert_eq!((player.balance, player.ticket, player.progress, player.lottery_info), (30, 40, 500, 6));
+        assert_eq!((player.last_lottery_timestamp, player.last_action_timestamp), (7, 8));
+        assert_eq!(
+            stored(&player),
+            vec![layout_tag(PLAYER_LAYOUT), 30, 40, 6, 500, ACTION_NONE, 7, 8, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn untagged_v0_player_with_the_magic_as_balance() {
+        // the packed word reads as layout_tag(2), the record length keeps it untagged
+        let player = load(vec![(0x7065_7473 << 32) | 2, 0, ACTION_NONE, 0, 0]);
+        assert_eq!((player.balance, player.ticket), (0x7065_7473, 2));
+    }
+
+    #[test]
+    fn v1_player() {
+        let player = load(vec![layout_tag(1), (30 << 32) | 40, (500 << 32) | 6, ACTION_NONE, 7, 8]);
+        assert_eq!((player.balance, player.ticket, player.progress, player.lottery_info), (30, 40, 500, 6));
+        assert_eq!((player.last_lottery_timestamp, player.last_action_timestamp), (7, 8));
+    }
+
+    #[test]
+    fn v2_player() {
+        let player = load(vec![layout_tag(2), 30, 40, 6, 500, ACTION_NONE, 7, 8]);
+        assert_eq!((player.balance, player.ticket, player.progress, player.lottery_info), (30, 40, 500, 6));
+        assert_eq!((player.last_checkin_timestamp, player.checkin_streak, player.referrer), (0, 0, [0, 0]));
+        assert_eq!(
+            stored(&player),
+            vec![layout_tag(PLAYER_LAYOUT), 30, 40, 6, 500, ACTION_NONE, 7, 8, 0, 0, 0, 0]
+        );
+    }
 }
@@ -0,0 +1,43 @@
+use crate::config::{get_referral_bonus, get_referral_share, RewardKind};
+use crate::error::PuppyError;
+use crate::player::PuppyPlayer;
+use crate::runtime::insert_event;
+
+// [referrer_1, referrer_2, referee_1, referee_2, bonus tickets of each party]
+pub const EVENT_REFERRAL: u64 = 0x08;
+// [referrer_1, referrer_2, referee_1, referee_2, reward kind, amount]
+pub const EVENT_REFERRAL_REWARD: u64 = 0x09;
+
+/// Record `referrer` on a newly installed player and pay both of them the
+/// referral bonus. The caller stores both players.
+pub fn refer(player: &mut PuppyPlayer, referrer: &mut PuppyPlayer) -> Result<(), PuppyError> {
+    let bonus = get_referral_bonus();
+    player.data.referrer = referrer.player_id;
+    player.data.add_ticket(bonus)?;
+    referrer.data.add_ticket(bonus)?;
+    let (r, p) = (referrer.player_id, player.player_id);
+    insert_event(EVENT_REFERRAL, &mut vec![r[0], r[1], p[0], p[1], bonus]);
+    Ok(())
+}
+
+/// Pay a lottery prize to `player`. The referrer's share is taken out of the
+/// prize, so a jackpot prize leaves the pool exactly once. Jackpot prizes are
+/// shared as balance like the prize itself. The caller stores the player.
+pub fn pay_prize(player: &mut PuppyPlayer, kind: RewardKind, amount: u64) -> Result<(), PuppyError> {
+    let r = player.data.referrer;
+    let share = if r == [0, 0] {
+        0
+    } else {
+        amount.checked_mul(get_referral_share()).ok_or(PuppyError::Overflow)? / 100
+    };
+    player.data.add_reward(kind, amount - share)?;
+    if share == 0 {
+        return Ok(());
+    }
+    let mut referrer = PuppyPlayer::get_from_pid(&r).ok_or(PuppyError::ReferrerNotExist)?;
+    referrer.data.add_reward(kind, share)?;
+    referrer.store();
+    let p = player.player_id;
+    insert_event(EVENT_REFERRAL_REWARD, &mut vec![r[0], r[1], p[0], p[1], kind as u64, share]);
+    Ok(())
+}
@@ -0,0 +1,77 @@
+use std::slice::IterMut;
+use serde::Serialize;
+use zkwasm_rest_abi::StorageData;
+use crate::config::ADMIN_PUBKEY;
+use crate::player::PuppyPlayer;
+use crate::runtime::Position;
+
+// role ids, a super admin holds every role
+pub const ROLE_SUPER_ADMIN: u64 = 0;
+pub const ROLE_TICKER: u64 = 1;
+pub const ROLE_DEPOSITOR: u64 = 2;
+pub const ROLE_MEME_CURATOR: u64 = 3;
+pub const ROLE_MODERATOR: u64 = 4;
+pub const ROLE_LOTTERY_OPERATOR: u64 = 5; // commits and reveals the lottery seeds
+pub const ROLE_OPERATOR: u64 = 6; // pauses and resumes commands
+
+pub const ROLES: [(&str, u64); 7] = [
+    ("ROLE_SUPER_ADMIN", ROLE_SUPER_ADMIN),
+    ("ROLE_TICKER", ROLE_TICKER),
+    ("ROLE_DEPOSITOR", ROLE_DEPOSITOR),
+    ("ROLE_MEME_CURATOR", ROLE_MEME_CURATOR),
+    ("ROLE_MODERATOR", ROLE_MODERATOR),
+    ("ROLE_LOTTERY_OPERATOR", ROLE_LOTTERY_OPERATOR),
+    ("ROLE_OPERATOR", ROLE_OPERATOR),
+];
+
+/// Roles granted to a player, keyed by pid
+#[derive(Clone, Serialize, Default, Copy, Debug)]
+pub struct RoleInfo {
+    pub roles: u64, // bit i is set if role id i is granted
+}
+
+impl StorageData for RoleInfo {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        RoleInfo {
+            roles: *u64data.next().unwrap(),
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(self.roles);
+    }
+}
+
+impl Position<RoleInfo> for RoleInfo {
+    const PREFIX: u64 = 0x1ff3;
+    const POSTFIX: u64 = 0xf1f3;
+    const EVENT_NAME: u64 = 0x0b;
+}
+
+impl RoleInfo {
+    pub fn set(pid: &[u64; 2], role: u64, granted: bool) {
+        let mut info = RoleInfo::get_or_new_position(pid, 0, RoleInfo::default());
+        if granted {
+            info.data.roles |= 1 << role;
+        } else {
+            info.data.roles &= !(1 << role);
+        }
+        info.store();
+        RoleInfo::emit_event(pid, 0, &info.data);
+    }
+}
+
+/// Make the key in admin.pubkey the first super admin so that it can grant the
+/// other roles. Runs once per state, the key can be revoked like any other.
+pub fn seed_super_admin() {
+    let pid = PuppyPlayer::pkey_to_pid(&ADMIN_PUBKEY);
+    let mut info = RoleInfo::get_or_new_position(&pid, 0, RoleInfo::default());
+    info.data.roles |= 1 << ROLE_SUPER_ADMIN;
+    info.store();
+}
+
+/// Whether the signer of a transaction holds `role`
+pub fn has_role(pkey: &[u64; 4], role: u64) -> bool {
+    let pid = PuppyPlayer::pkey_to_pid(pkey);
+    RoleInfo::get_position(&pid, 0)
+        .map_or(false, |info| info.data.roles & ((1 << role) | (1 << ROLE_SUPER_ADMIN)) != 0)
+}
@@ -0,0 +1,17 @@
+//! Host bindings used by the game logic.
+//!
+//! Inside zkWasm these come from zkwasm-rest-abi / zkwasm-rest-convention; with
+//! the `native` feature they are replaced by the in-memory backend in `native.rs`.
+
+#[cfg(not(feature = "native"))]
+pub use zkwasm_rest_abi::{enforce, Player, MERKLE_MAP};
+#[cfg(not(feature = "native"))]
+pub use zkwasm_rest_convention::{clear_events, insert_event, IndexedObject, Position, Wrapped};
+#[cfg(not(feature = "native"))]
+pub use zkwasm_rust_sdk::require;
+
+#[cfg(feature = "native")]
+pub use crate::native::{
+    clear_events, enforce, insert_event, require, IndexedObject, Player, Position, Wrapped,
+    MERKLE_MAP,
+};
@@ -0,0 +1,106 @@
+use std::slice::IterMut;
+use serde::Serialize;
+use zkwasm_rest_abi::StorageData;
+use crate::runtime::IndexedObject;
+use crate::meme::MemeInfo;
+use crate::error::PuppyError;
+
+#[derive(Clone, Serialize, Default, Copy, Debug)]
+pub struct Season {
+    pub id: u64,    // 0 before the first season is opened
+    pub start: u64, // counter when the season was opened
+    pub end: u64,   // counter when the season was closed, 0 while it is running
+}
+
+impl Season {
+    pub fn is_open(&self) -> bool {
+        self.id != 0 && self.end == 0
+    }
+
+    pub fn open(&mut self, counter: u64) -> Result<(), PuppyError> {
+        if self.is_open() {
+            Err(PuppyError::SeasonAlreadyOpen)
+        } else {
+            self.id += 1;
+            self.start = counter;
+            self.end = 0;
+            Ok(())
+        }
+    }
+
+    pub fn close(&mut self, counter: u64) -> Result<(), PuppyError> {
+        if self.is_open() {
+            self.end = counter;
+            Ok(())
+        } else {
+            Err(PuppyError::SeasonNotOpen)
+        }
+    }
+}
+
+/// Frozen leaderboard entry of a meme at the end of a season
+#[derive(Clone, Serialize, Default, Copy)]
+pub struct SeasonMemeInfo {
+    pub season: u64,
+    pub id: u64,
+    pub rank: u64, // votes cast during the season
+    pub stake: u64,
+    pub owner: [u64; 2],
+}
+
+impl SeasonMemeInfo {
+    // meme ids are expected to fit in 32 bits
+    pub fn index(season: u64, meme_id: u64) -> u64 {
+        (season << 32) + (meme_id & 0xffffffff)
+    }
+}
+
+impl StorageData for SeasonMemeInfo {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        SeasonMemeInfo {
+            season: *u64data.next().unwrap(),
+            id: *u64data.next().unwrap(),
+            rank: *u64data.next().unwrap(),
+            stake: *u64data.next().unwrap(),
+            owner: [*u64data.next().unwrap(), *u64data.next().unwrap()],
+        }
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(self.season);
+        data.push(self.id);
+        data.push(self.rank);
+        data.push(self.stake);
+        data.push(self.owner[0]);
+        data.push(self.owner[1]);
+    }
+}
+
+impl IndexedObject<SeasonMemeInfo> for SeasonMemeInfo {
+    const PREFIX: u64 = 0x1ee2;
+    const POSTFIX: u64 = 0xfee2;
+    const EVENT_NAME: u64 = 0x03;
+}
+
+/// Freeze the season rank and stake of every installed meme into the archive
+/// of the given season and start the season ranks of the next season from zero.
+/// The all time rank that staking yield is paid against and the stakes are
+/// carried over untouched.
+pub fn rollover(season: u64, memes: &[u64]) {
+    for meme_id in memes {
+        if let Some(mut meme) = MemeInfo::get_object(*meme_id) {
+            let index = SeasonMemeInfo::index(season, *meme_id);
+            let archived = SeasonMemeInfo::new_object(SeasonMemeInfo {
+                season,
+                id: *meme_id,
+                rank: meme.data.season_rank,
+                stake: meme.data.stake,
+                owner: meme.data.owner,
+            }, index);
+            archived.store();
+            SeasonMemeInfo::emit_event(index, &archived.data);
+            meme.data.season_rank = 0;
+            meme.store();
+            MemeInfo::emit_event(*meme_id, &meme.data);
+        }
+    }
+}
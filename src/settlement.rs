@@ -12,6 +12,7 @@ impl SettlementInfo {
         return sinfo.0.len()
     }
     pub fn flush_settlement() -> Vec<u8> {
+        #[cfg(not(feature = "native"))]
         zkwasm_rust_sdk::dbg!("flush settlement\n");
         let sinfo = unsafe { &mut SETTLEMENT };
         let mut bytes: Vec<u8> = Vec::with_capacity(sinfo.0.len() * 32);
@@ -1,21 +1,33 @@
-use crate::config::ADMIN_PUBKEY;
-use crate::meme::MemeInfo;
+use crate::config::{get_airdrop_amount, RuntimeConfig, ACTION_JUMP, ACTION_SHAKE_FEET, ACTION_SHAKE_HEAD, CONFIG_PARAMS};
+use crate::meme::{MemeInfo, MAX_MEME_ID};
+use crate::season::Season;
+use crate::lottery::SeedCommitment;
 use crate::player::{Owner, PuppyPlayer};
 use crate::settlement::SettlementInfo;
-use crate::Player;
+use crate::supply::{mint, Asset, AssetSupply, Supply};
+use crate::referral::refer;
+use crate::runtime::Player;
 use serde::Serialize;
 use std::cell::RefCell;
-use zkwasm_rest_abi::MERKLE_MAP;
-use zkwasm_rust_sdk::require;
-use zkwasm_rest_abi::enforce;
+use crate::runtime::MERKLE_MAP;
+use crate::runtime::require;
+use crate::runtime::enforce;
 use crate::command::Command;
 use crate::command::Activity;
 use crate::command::Deposit;
 use crate::command::Withdraw;
 use crate::command::WithdrawLottery;
 use crate::command::CommandHandler;
-use crate::error::*;
-use zkwasm_rest_convention::{clear_events, IndexedObject};
+use crate::command::SeasonCommand;
+use crate::command::SeedCommand;
+use crate::command::UpdateConfig;
+use crate::command::RoleCommand;
+use crate::command::SetPause;
+use crate::role::{has_role, seed_super_admin, ROLES, ROLE_DEPOSITOR, ROLE_LOTTERY_OPERATOR, ROLE_MEME_CURATOR, ROLE_MODERATOR, ROLE_OPERATOR, ROLE_SUPER_ADMIN, ROLE_TICKER};
+use crate::error::{DecodeError, PuppyError};
+use crate::runtime::{clear_events, IndexedObject};
+use core::slice::IterMut;
+use zkwasm_rest_abi::StorageData;
 
 
 #[derive(Serialize)]
@@ -23,26 +35,98 @@ pub struct GlobalState {
     pub counter: u64,
     pub txsize: u64,
     pub airdrop: u64,
+    pub season: Season,
+    pub memes: Vec<u64>,
+    pub lottery_epoch: u64,
+    pub jackpot: u64,
+    pub supply: AssetSupply,
+    pub treasury: u64, // balance spent on tickets
+    pub paused: u64,   // bit i set pauses player commands with opcode i
+    pub roles_seeded: bool, // the admin key was granted super admin
+    pub params: RuntimeConfig,
+}
+
+#[derive(Serialize)]
+pub struct PlayerState {
+    #[serde(flatten)]
+    player: PuppyPlayer,
+    lottery_remaining: u64,
 }
 
 #[derive(Serialize)]
 pub struct QueryState {
     counter: u64,
     airdrop: u64,
+    season: Season,
+    lottery_epoch: u64,
+    seed_commitment: Option<SeedCommitment>,
+    jackpot: u64,
+    supply: AssetSupply,
+    treasury: u64,
+    paused: u64,
 }
 
-const TICK: u64 = 0;
-const INSTALL_PLAYER: u64 = 1;
+// The stored global state starts with STATE_MAGIC | version. Roots written
+// before versioning (version 0) start directly with the counter.
+// New fields are only ever appended, missing trailing fields load as 0.
+const STATE_MAGIC: u64 = 0x7075_7070_0000_0000;
+const STATE_VERSION: u64 = 6;
+
+pub const TICK: u64 = 0;
+pub const INSTALL_PLAYER: u64 = 1;
 
-const VOTE: u64 = 2;
-const STAKE: u64 = 3;
-const COLLECT: u64 = 4;
-const COMMENT: u64 = 5;
-const LOTTERY: u64 = 6;
-const INSTALL_MEME: u64 = 7;
-const WITHDRAW: u64 = 8;
-const DEPOSIT: u64 = 9;
-const WITHDRAW_LOTTERY: u64 = 10;
+pub const VOTE: u64 = 2;
+pub const STAKE: u64 = 3;
+pub const COLLECT: u64 = 4;
+pub const COMMENT: u64 = 5;
+pub const LOTTERY: u64 = 6;
+pub const INSTALL_MEME: u64 = 7;
+pub const WITHDRAW: u64 = 8;
+pub const DEPOSIT: u64 = 9;
+pub const WITHDRAW_LOTTERY: u64 = 10;
+pub const OPEN_SEASON: u64 = 11;
+pub const CLOSE_SEASON: u64 = 12;
+pub const UNSTAKE: u64 = 13;
+pub const COMMIT_SEED: u64 = 14;
+pub const REVEAL_SEED: u64 = 15;
+pub const BUY_TICKETS: u64 = 16;
+pub const CHECK_IN: u64 = 17;
+pub const SHAKE_FEET: u64 = 18;
+pub const SHAKE_HEAD: u64 = 19;
+pub const JUMP: u64 = 20;
+pub const UPDATE_CONFIG: u64 = 21;
+pub const GRANT_ROLE: u64 = 22;
+pub const REVOKE_ROLE: u64 = 23;
+pub const SET_PAUSE: u64 = 24;
+
+// opcode names as exposed to clients
+pub const COMMANDS: [(&str, u64); 25] = [
+    ("TICK", TICK),
+    ("INSTALL_PLAYER", INSTALL_PLAYER),
+    ("VOTE", VOTE),
+    ("STAKE", STAKE),
+    ("COLLECT", COLLECT),
+    ("COMMENT", COMMENT),
+    ("LOTTERY", LOTTERY),
+    ("INSTALL_MEME", INSTALL_MEME),
+    ("WITHDRAW", WITHDRAW),
+    ("DEPOSIT", DEPOSIT),
+    ("WITHDRAW_LOTTERY", WITHDRAW_LOTTERY),
+    ("OPEN_SEASON", OPEN_SEASON),
+    ("CLOSE_SEASON", CLOSE_SEASON),
+    ("UNSTAKE", UNSTAKE),
+    ("COMMIT_SEED", COMMIT_SEED),
+    ("REVEAL_SEED", REVEAL_SEED),
+    ("BUY_TICKETS", BUY_TICKETS),
+    ("CHECK_IN", CHECK_IN),
+    ("SHAKE_FEET", SHAKE_FEET),
+    ("SHAKE_HEAD", SHAKE_HEAD),
+    ("JUMP", JUMP),
+    ("UPDATE_CONFIG", UPDATE_CONFIG),
+    ("GRANT_ROLE", GRANT_ROLE),
+    ("REVOKE_ROLE", REVOKE_ROLE),
+    ("SET_PAUSE", SET_PAUSE),
+];
 
 
 
@@ -51,19 +135,40 @@ impl GlobalState {
         GlobalState {
             counter: 0,
             txsize: 0,
-            airdrop: 10000000
+            airdrop: 10000000,
+            season: Season::default(),
+            memes: vec![],
+            lottery_epoch: 0,
+            jackpot: 0,
+            supply: AssetSupply::default(),
+            treasury: 0,
+            paused: 0,
+            roles_seeded: false,
+            params: RuntimeConfig::default(),
         }
     }
 
     pub fn snapshot() -> String {
         let counter = GLOBAL_STATE.0.borrow().counter;
         let airdrop = GLOBAL_STATE.0.borrow().airdrop;
-        serde_json::to_string(&QueryState { counter, airdrop }).unwrap()
+        let season = GLOBAL_STATE.0.borrow().season;
+        let lottery_epoch = GLOBAL_STATE.0.borrow().lottery_epoch;
+        let seed_commitment = SeedCommitment::get_object(lottery_epoch).map(|c| c.data);
+        let jackpot = GLOBAL_STATE.0.borrow().jackpot;
+        let supply = GLOBAL_STATE.0.borrow().supply;
+        let treasury = GLOBAL_STATE.0.borrow().treasury;
+        let paused = GLOBAL_STATE.0.borrow().paused;
+        serde_json::to_string(&QueryState { counter, airdrop, season, lottery_epoch, seed_commitment, jackpot, supply, treasury, paused }).unwrap()
     }
 
     pub fn get_state(pid: Vec<u64>) -> String {
         let player = PuppyPlayer::get(&pid.try_into().unwrap());
-        serde_json::to_string(&player).unwrap()
+        let counter = GLOBAL_STATE.0.borrow().counter;
+        let state = player.map(|player| {
+            let lottery_remaining = player.data.lottery_remaining(counter);
+            PlayerState { player, lottery_remaining }
+        });
+        serde_json::to_string(&state).unwrap()
     }
 
     pub fn preempt() -> bool {
@@ -77,7 +182,9 @@ impl GlobalState {
         }
     }
 
+    // seals the bundle, txsize counts the transactions of the next one
     pub fn flush_settlement() -> Vec<u8> {
+        GLOBAL_STATE.0.borrow_mut().txsize = 0;
         SettlementInfo::flush_settlement()
     }
 
@@ -87,8 +194,7 @@ impl GlobalState {
 
     pub fn store_into_kvpair(&self) {
         let mut v = vec![];
-        v.push(self.counter);
-        v.push(self.airdrop);
+        self.to_data(&mut v);
         let kvpair = unsafe { &mut MERKLE_MAP };
         kvpair.set(&[0, 0, 0, 0], v.as_slice());
     }
@@ -97,11 +203,7 @@ impl GlobalState {
         let kvpair = unsafe { &mut MERKLE_MAP };
         let mut data = kvpair.get(&[0, 0, 0, 0]);
         if !data.is_empty() {
-            let mut u64data = data.iter_mut();
-            let counter = *u64data.next().unwrap();
-            let airdrop = *u64data.next().unwrap();
-            self.counter = counter;
-            self.airdrop = airdrop;
+            *self = GlobalState::from_data(&mut data.iter_mut());
         }
     }
 
@@ -111,6 +213,10 @@ impl GlobalState {
 
     pub fn initialize() {
         GLOBAL_STATE.0.borrow_mut().fetch();
+        if !GLOBAL_STATE.0.borrow().roles_seeded {
+            seed_super_admin();
+            GLOBAL_STATE.0.borrow_mut().roles_seeded = true;
+        }
     }
 
     pub fn get_counter() -> u64 {
@@ -118,6 +224,69 @@ impl GlobalState {
     }
 }
 
+impl StorageData for GlobalState {
+    fn from_data(u64data: &mut IterMut<u64>) -> Self {
+        let first = *u64data.next().unwrap();
+        let version = if first & 0xffffffff00000000 == STATE_MAGIC {
+            first & 0xffffffff
+        } else {
+            0
+        };
+        unsafe { require(version <= STATE_VERSION) };
+        let mut next = || u64data.next().map_or(0, |x| *x);
+        let counter = if version == 0 { first } else { next() };
+        let airdrop = next();
+        let season = Season {
+            id: next(),
+            start: next(),
+            end: next(),
+        };
+        let meme_count = next();
+        let memes = (0..meme_count).map(|_| next()).collect();
+        let mut state = GlobalState {
+            counter,
+            airdrop,
+            season,
+            memes,
+            lottery_epoch: next(),
+            jackpot: next(),
+            txsize: next(),
+            supply: AssetSupply {
+                ticket: Supply { minted: next(), burned: next() },
+                balance: Supply { minted: next(), burned: next() },
+                lottery_token: Supply { minted: next(), burned: next() },
+            },
+            treasury: next(),
+            paused: next(),
+            roles_seeded: next() != 0,
+            params: RuntimeConfig::default(),
+        };
+        state.params = RuntimeConfig::from_data(u64data);
+        state
+    }
+    fn to_data(&self, data: &mut Vec<u64>) {
+        data.push(STATE_MAGIC | STATE_VERSION);
+        data.push(self.counter);
+        data.push(self.airdrop);
+        data.push(self.season.id);
+        data.push(self.season.start);
+        data.push(self.season.end);
+        data.push(self.memes.len() as u64);
+        data.extend_from_slice(&self.memes);
+        data.push(self.lottery_epoch);
+        data.push(self.jackpot);
+        data.push(self.txsize);
+        for supply in [self.supply.ticket, self.supply.balance, self.supply.lottery_token] {
+            data.push(supply.minted);
+            data.push(supply.burned);
+        }
+        data.push(self.treasury);
+        data.push(self.paused);
+        data.push(self.roles_seeded as u64);
+        self.params.to_data(data);
+    }
+}
+
 pub struct SafeState(pub RefCell<GlobalState>);
 unsafe impl Sync for SafeState {}
 
@@ -130,63 +299,183 @@ pub struct Transaction {
     nonce: u64,
 }
 
+impl Transaction {
+    pub fn command(&self) -> &Command {
+        &self.command
+    }
+
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+}
+
 impl Transaction {
     pub fn decode_error(e: u32) -> &'static str {
         crate::command::decode_error(e)
     }
 
+    /// Decode a transaction, trapping on malformed input
     pub fn decode(params: &[u64]) -> Self {
-        let command = params[0] & 0xff;
-        let nonce = params[0] >> 16;
+        match Self::try_decode(params) {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                enforce(false, e.name());
+                unreachable!()
+            }
+        }
+    }
+
+    pub fn try_decode(params: &[u64]) -> Result<Self, DecodeError> {
+        let header = *params.first().ok_or(DecodeError::MissingParams {
+            command: 0,
+            expected: 1,
+            found: 0,
+        })?;
+        let command = header & 0xff;
+        let nonce = header >> 16;
+        let expect = |expected: usize| {
+            if params.len() < expected {
+                Err(DecodeError::MissingParams { command, expected, found: params.len() })
+            } else {
+                Ok(())
+            }
+        };
+        let meme_id = |id: u64| {
+            if id > MAX_MEME_ID {
+                Err(DecodeError::InvalidMemeIndex(id))
+            } else {
+                Ok(id as usize)
+            }
+        };
         let command = if command == WITHDRAW {
+            expect(5)?;
             Command::Withdraw (Withdraw {
                 data: [params[2], params[3], params[4]]
             })
         } else if command == DEPOSIT {
-            enforce(params[3] == 0, "check deposit index"); // only token index 0 is supported
+            expect(5)?;
+            // only token index 0 is supported
+            if params[3] != 0 {
+                return Err(DecodeError::InvalidTokenIndex(params[3]));
+            }
             Command::Deposit (Deposit {
                 data: [params[1], params[2], params[4]]
             })
         } else if command == WITHDRAW_LOTTERY {
+            expect(5)?;
             Command::WithdrawLottery (WithdrawLottery {
                 data: [params[2], params[3], params[4]]
             })
+        } else if command == OPEN_SEASON {
+            Command::Season (SeasonCommand::Open)
+        } else if command == CLOSE_SEASON {
+            Command::Season (SeasonCommand::Close)
+        } else if command == COMMIT_SEED {
+            expect(5)?;
+            Command::Seed (SeedCommand::Commit([params[1], params[2], params[3], params[4]]))
+        } else if command == REVEAL_SEED {
+            expect(5)?;
+            Command::Seed (SeedCommand::Reveal([params[1], params[2], params[3], params[4]]))
+        } else if command == UPDATE_CONFIG {
+            expect(3)?;
+            if params[1] >= CONFIG_PARAMS.len() as u64 {
+                return Err(DecodeError::InvalidConfigParam(params[1]));
+            }
+            Command::UpdateConfig (UpdateConfig {
+                param: params[1],
+                value: params[2],
+            })
+        } else if command == GRANT_ROLE || command == REVOKE_ROLE {
+            expect(4)?;
+            if params[1] >= ROLES.len() as u64 {
+                return Err(DecodeError::InvalidRole(params[1]));
+            }
+            let pid = [params[2], params[3]];
+            if command == GRANT_ROLE {
+                Command::Role (RoleCommand::Grant(params[1], pid))
+            } else {
+                Command::Role (RoleCommand::Revoke(params[1], pid))
+            }
+        } else if command == SET_PAUSE {
+            expect(2)?;
+            Command::SetPause (SetPause {
+                paused: params[1],
+            })
         } else if command == INSTALL_PLAYER {
-            Command::InstallPlayer
+            // params are zero padded by some clients, a zero pid means no referrer
+            let referrer = match params.get(1..3) {
+                Some([0, 0]) | None => None,
+                Some(pid) => Some([pid[0], pid[1]]),
+            };
+            Command::InstallPlayer (referrer)
         } else if command == INSTALL_MEME {
-            Command::InstallMeme (params[1] as u64)
+            expect(2)?;
+            Command::InstallMeme (meme_id(params[1])? as u64)
         } else  if command == LOTTERY {
             Command::Activity (Activity::Lottery)
         } else if command == VOTE {
-            Command::Activity (Activity::Vote(params[1] as usize))
+            expect(2)?;
+            Command::Activity (Activity::Vote(meme_id(params[1])?))
         } else if command == STAKE {
-            Command::Activity (Activity::Stake(params[1] as usize, params[2]))
+            expect(3)?;
+            Command::Activity (Activity::Stake(meme_id(params[1])?, params[2]))
+        } else if command == UNSTAKE {
+            expect(3)?;
+            Command::Activity (Activity::Unstake(meme_id(params[1])?, params[2]))
+        } else if command == BUY_TICKETS {
+            expect(2)?;
+            Command::Activity (Activity::BuyTickets(params[1]))
+        } else if command == SHAKE_FEET {
+            Command::Activity (Activity::Act(ACTION_SHAKE_FEET))
+        } else if command == SHAKE_HEAD {
+            Command::Activity (Activity::Act(ACTION_SHAKE_HEAD))
+        } else if command == JUMP {
+            Command::Activity (Activity::Act(ACTION_JUMP))
+        } else if command == CHECK_IN {
+            Command::Activity (Activity::CheckIn)
         } else if command == COLLECT {
-            Command::Activity (Activity::Collect(params[1] as usize))
+            expect(2)?;
+            Command::Activity (Activity::Collect(meme_id(params[1])?))
         } else if command == COMMENT {
-            let chars = params[1..].iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
-            Command::Activity (Activity::Comment(chars))
-        } else {
-            unsafe {require(command == TICK)};
+            expect(3)?;
+            let mut chars = params[2..].iter().flat_map(|x| x.to_le_bytes()).collect::<Vec<u8>>();
+            // drop the zero padding of the last param word
+            let len = chars.iter().rposition(|c| *c != 0).map_or(0, |p| p + 1);
+            chars.truncate(len);
+            Command::Activity (Activity::Comment(meme_id(params[1])?, chars))
+        } else if command == TICK {
             Command::Tick
+        } else {
+            return Err(DecodeError::UnknownCommand(command));
         };
-        Transaction {
+        Ok(Transaction {
             command,
             nonce,
-        }
+        })
     }
 
-    pub fn create_player(&self, pkey: &[u64; 4]) -> Result<(), u32> {
+    pub fn create_player(&self, pkey: &[u64; 4], referrer: &Option<[u64; 2]>) -> Result<(), PuppyError> {
         let player = PuppyPlayer::get(pkey);
         match player {
-            Some(_) => Err(ERROR_PLAYER_ALREADY_EXIST),
+            Some(_) => Err(PuppyError::PlayerAlreadyExist),
             None => {
+                let mut referrer = match referrer {
+                    Some(pid) => Some(PuppyPlayer::get_from_pid(pid).ok_or(PuppyError::ReferrerNotExist)?),
+                    None => None,
+                };
                 let mut player = Player::new(pkey);
-                if GLOBAL_STATE.0.borrow().airdrop > 50 {
-                    player.data.balance = 50;
-                    GLOBAL_STATE.0.borrow_mut().airdrop -= 50;
-                } else {
-                    player.data.balance = 0;
+                // the initial tickets of PlayerData::default
+                mint(Asset::Ticket, player.data.ticket);
+                if let Some(referrer) = referrer.as_mut() {
+                    refer(&mut player, referrer)?;
+                }
+                let airdrop = get_airdrop_amount();
+                if GLOBAL_STATE.0.borrow().airdrop > airdrop {
+                    player.data.add_balance(airdrop)?;
+                    GLOBAL_STATE.0.borrow_mut().airdrop -= airdrop;
+                }
+                if let Some(referrer) = referrer {
+                    referrer.store();
                 }
                 player.store();
                 Ok(())
@@ -194,11 +483,28 @@ impl Transaction {
         }
     }
 
-    pub fn create_meme(&self, id: u64) -> Result<(), u32> {
-        let meme = MemeInfo::new_object(MemeInfo::default(), id);
-        meme.store();
-        MemeInfo::emit_event(id, &meme.data);
-        Ok(())
+    pub fn create_meme(&self, pid: &[u64; 2], id: u64) -> Result<(), PuppyError> {
+        let mut admin = PuppyPlayer::get_from_pid(pid).map_or(Err(PuppyError::PlayerNotExist), |x| Ok(x))?;
+        admin.check_and_inc_nonce(self.nonce);
+        let listed = GLOBAL_STATE.0.borrow().memes.contains(&id);
+        match MemeInfo::get_object(id) {
+            Some(_) if listed => Err(PuppyError::MemeAlreadyExist),
+            Some(_) => {
+                // installed before the meme list was kept, the service installs
+                // every meme again at startup which fills the list in
+                GLOBAL_STATE.0.borrow_mut().memes.push(id);
+                admin.store();
+                Ok(())
+            },
+            None => {
+                let meme = MemeInfo::new_object(MemeInfo::default(), id);
+                meme.store();
+                GLOBAL_STATE.0.borrow_mut().memes.push(id);
+                admin.store();
+                MemeInfo::emit_event(id, &meme.data);
+                Ok(())
+            }
+        }
     }
 
 
@@ -213,28 +519,66 @@ impl Transaction {
     pub fn process(&self, pkey: &[u64; 4], rand: &[u64; 4]) -> Vec<u64> {
         let pid = PuppyPlayer::pkey_to_pid(&pkey);
         let counter = GLOBAL_STATE.0.borrow_mut().counter;
+        let supply = GLOBAL_STATE.0.borrow().supply;
+        let paused = GLOBAL_STATE.0.borrow().paused;
+        if self.command.pausable() && paused & (1 << self.command.opcode()) != 0 {
+            let txsize = GLOBAL_STATE.0.borrow().txsize;
+            return clear_events(vec![PuppyError::CommandPaused.code() as u64, txsize]);
+        }
         let e = match &self.command {
             Command::Tick => {
-                enforce(*pkey == *ADMIN_PUBKEY, "check admin key of tick");
+                enforce(has_role(pkey, ROLE_TICKER), "check ticker role of tick");
                 self.tick();
                 0
             },
-            Command::InstallPlayer => self.create_player(pkey)
-                .map_or_else(|e| e, |_| 0),
-            Command::InstallMeme(id)=> self.create_meme(*id)
-                .map_or_else(|e| e, |_| 0),
+            Command::InstallPlayer(referrer) => self.create_player(pkey, referrer)
+                .map_or_else(u32::from, |_| 0),
+            Command::InstallMeme(id) => {
+                enforce(has_role(pkey, ROLE_MEME_CURATOR), "check meme curator role of install meme");
+                self.create_meme(&pid, *id)
+                    .map_or_else(u32::from, |_| 0)
+            },
             Command::Withdraw(cmd) => cmd.handle(&pid, self.nonce, rand, counter)
-                .map_or_else(|e| e, |_| 0),
+                .map_or_else(u32::from, |_| 0),
             Command::WithdrawLottery(cmd) => cmd.handle(&pid, self.nonce, rand, counter)
-                .map_or_else(|e| e, |_| 0),
+                .map_or_else(u32::from, |_| 0),
             Command::Activity(cmd) => cmd.handle(&pid, self.nonce, rand, counter)
-                .map_or_else(|e| e, |_| 0),
+                .map_or_else(u32::from, |_| 0),
             Command::Deposit(cmd) => {
-                enforce(*pkey == *ADMIN_PUBKEY, "check admin key of deposit");
+                enforce(has_role(pkey, ROLE_DEPOSITOR), "check depositor role of deposit");
+                cmd.handle(&pid, self.nonce, rand, counter)
+                    .map_or_else(u32::from, |_| 0)
+            },
+            Command::Season(cmd) => {
+                enforce(has_role(pkey, ROLE_MODERATOR), "check moderator role of season");
+                cmd.handle(&pid, self.nonce, rand, counter)
+                    .map_or_else(u32::from, |_| 0)
+            },
+            Command::Seed(cmd) => {
+                enforce(has_role(pkey, ROLE_LOTTERY_OPERATOR), "check lottery operator role of lottery seed");
                 cmd.handle(&pid, self.nonce, rand, counter)
-                    .map_or_else(|e| e, |_| 0)
+                    .map_or_else(u32::from, |_| 0)
+            },
+            Command::UpdateConfig(cmd) => {
+                enforce(has_role(pkey, ROLE_SUPER_ADMIN), "check super admin role of update config");
+                cmd.handle(&pid, self.nonce, rand, counter)
+                    .map_or_else(u32::from, |_| 0)
+            },
+            Command::Role(cmd) => {
+                enforce(has_role(pkey, ROLE_SUPER_ADMIN), "check super admin role of role management");
+                cmd.handle(&pid, self.nonce, rand, counter)
+                    .map_or_else(u32::from, |_| 0)
+            },
+            Command::SetPause(cmd) => {
+                enforce(has_role(pkey, ROLE_OPERATOR), "check operator role of pause");
+                cmd.handle(&pid, self.nonce, rand, counter)
+                    .map_or_else(u32::from, |_| 0)
             },
         };
+        if e != 0 {
+            // a failed command may have spent or credited player data it never stored
+            GLOBAL_STATE.0.borrow_mut().supply = supply;
+        }
         if e == 0 {
             // if no error occurred
             match self.command {
@@ -0,0 +1,54 @@
+//! Versioned storage layouts for player, meme and stake records.
+//!
+//! Records are written as `LAYOUT_MAGIC | version` followed by the fields of
+//! that version. Records without the tag were written before versioning and
+//! are read as version 0. Old layouts are migrated when they are loaded and
+//! written back in the latest layout on the next store.
+//!
+//! An untagged first word can hold any value, e.g. the packed balance of a
+//! version 0 player may start with the magic. Every tagged layout is longer
+//! than the untagged one of the same record, so records no longer than the
+//! untagged layout are read as version 0 whatever their first word.
+
+use core::slice::IterMut;
+
+const LAYOUT_MAGIC: u64 = 0x7065_7473_0000_0000;
+const LAYOUT_MASK: u64 = 0xffff_ffff_0000_0000;
+
+pub fn layout_tag(version: u64) -> u64 {
+    LAYOUT_MAGIC | version
+}
+
+/// Split a stored record into its layout version and its fields.
+/// `untagged_len` is the most words a version 0 record of this kind has.
+pub fn read_layout(u64data: &mut IterMut<u64>, untagged_len: usize) -> (u64, std::vec::IntoIter<u64>) {
+    let words = u64data.map(|x| *x).collect::<Vec<u64>>();
+    match words.first() {
+        Some(tag) if words.len() > untagged_len && tag & LAYOUT_MASK == LAYOUT_MAGIC => {
+            (tag & !LAYOUT_MASK, words[1..].to_vec().into_iter())
+        }
+        _ => (0, words.into_iter()),
+    }
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagged_record() {
+        let mut data = vec![layout_tag(2), 1, 2, 3];
+        let (version, fields) = read_layout(&mut data.iter_mut(), 2);
+        assert_eq!(version, 2);
+        assert_eq!(fields.collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn untagged_record_starting_with_the_magic() {
+        let first = LAYOUT_MAGIC | 2;
+        let mut data = vec![first, 7];
+        let (version, fields) = read_layout(&mut data.iter_mut(), 2);
+        assert_eq!(version, 0);
+        assert_eq!(fields.collect::<Vec<_>>(), vec![first, 7]);
+    }
+}
@@ -0,0 +1,65 @@
+//! Total minted and burned amounts of every player asset.
+//!
+//! The `PlayerData` helpers that credit or spend an asset record it here, so
+//! the circulating supply (minted - burned) always equals the sum held by all
+//! players. Totals wrap on overflow, the difference stays exact modulo 2^64.
+
+use serde::Serialize;
+use crate::state::GLOBAL_STATE;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Asset {
+    Ticket = 0,
+    Balance = 1,
+    LotteryToken = 2,
+}
+
+#[derive(Clone, Copy, Serialize, Default, Debug, PartialEq)]
+pub struct Supply {
+    pub minted: u64,
+    pub burned: u64,
+}
+
+impl Supply {
+    pub fn circulating(&self) -> u64 {
+        self.minted.wrapping_sub(self.burned)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Default, Debug, PartialEq)]
+pub struct AssetSupply {
+    pub ticket: Supply,
+    pub balance: Supply,
+    pub lottery_token: Supply,
+}
+
+impl AssetSupply {
+    fn get_mut(&mut self, asset: Asset) -> &mut Supply {
+        match asset {
+            Asset::Ticket => &mut self.ticket,
+            Asset::Balance => &mut self.balance,
+            Asset::LotteryToken => &mut self.lottery_token,
+        }
+    }
+
+    /// Circulating supply indexed by `Asset`
+    pub fn circulating(&self) -> [u64; 3] {
+        [
+            self.ticket.circulating(),
+            self.balance.circulating(),
+            self.lottery_token.circulating(),
+        ]
+    }
+}
+
+pub fn mint(asset: Asset, amount: u64) {
+    let mut state = GLOBAL_STATE.0.borrow_mut();
+    let supply = state.supply.get_mut(asset);
+    supply.minted = supply.minted.wrapping_add(amount);
+}
+
+pub fn burn(asset: Asset, amount: u64) {
+    let mut state = GLOBAL_STATE.0.borrow_mut();
+    let supply = state.supply.get_mut(asset);
+    supply.burned = supply.burned.wrapping_add(amount);
+}
@@ -0,0 +1,248 @@
+//! End to end scenarios against the native in-memory store.
+//!
+//! cargo test --features native
+
+#![cfg(feature = "native")]
+
+use std::sync::{Mutex, MutexGuard};
+use sha2::{Digest, Sha256};
+use puppy_party::command::{Activity, Command, SeasonCommand, SeedCommand, SetPause, Withdraw, EVENT_PAUSE_UPDATE};
+use puppy_party::config::{get_initial_tickets, ADMIN_PUBKEY};
+use puppy_party::error::PuppyError;
+use puppy_party::lottery::{LotteryDraw, EVENT_LOTTERY_PRIZE};
+use puppy_party::meme::{MemeInfo, StakeInfo};
+use puppy_party::season::SeasonMemeInfo;
+use puppy_party::native;
+use puppy_party::player::{Owner, PuppyPlayer};
+use puppy_party::runtime::{IndexedObject, Position};
+use puppy_party::state::GlobalState;
+
+const PLAYER: [u64; 4] = [1, 2, 3, 4];
+const PID: [u64; 2] = [2, 3];
+const MEME: u64 = 7;
+
+// the store is global to the process, scenarios run one at a time
+static STORE: Mutex<()> = Mutex::new(());
+
+fn start() -> MutexGuard<'static, ()> {
+    let guard = STORE.lock().unwrap_or_else(|e| e.into_inner());
+    native::reset();
+    guard
+}
+
+// sign `command` with the next nonce of `pkey`, returns [error, txsize, events...]
+fn run(pkey: &[u64; 4], command: Command) -> Vec<u64> {
+    let nonce = PuppyPlayer::get(pkey).map_or(0, |p| p.nonce);
+    native::run_transaction(pkey, &command.encode(nonce), &[0; 4])
+}
+
+fn ok(pkey: &[u64; 4], command: Command) -> Vec<u64> {
+    let r = run(pkey, command.clone());
+    assert_eq!(r[0], 0, "{:?} failed with {}", command, puppy_party::command::decode_error(r[0] as u32));
+    r
+}
+
+fn fails(pkey: &[u64; 4], command: Command, e: PuppyError) {
+    assert_eq!(run(pkey, command)[0], e.code() as u64);
+}
+
+fn tick(n: u64) {
+    for _ in 0..n {
+        ok(&ADMIN_PUBKEY, Command::Tick);
+    }
+}
+
+fn player() -> PuppyPlayer {
+    PuppyPlayer::get_from_pid(&PID).unwrap()
+}
+
+// data of the first event `typ` in the result of a transaction
+fn event(r: &[u64], typ: u64) -> Option<Vec<u64>> {
+    let mut i = 2;
+    while i < r.len() {
+        let len = (r[i] & 0xffffffff) as usize;
+        if r[i] >> 32 == typ {
+            return Some(r[i + 1..i + 1 + len].to_vec());
+        }
+        i += 1 + len;
+    }
+    None
+}
+
+// install the admin, a player and a meme
+fn setup() {
+    ok(&ADMIN_PUBKEY, Command::InstallPlayer(None));
+    ok(&PLAYER, Command::InstallPlayer(None));
+    ok(&ADMIN_PUBKEY, Command::InstallMeme(MEME));
+}
+
+// vote until the lottery progress bar is full
+fn fill_progress() {
+    while player().data.progress < 1000 {
+        tick(2);
+        ok(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)));
+    }
+}
+
+#[test]
+fn install() {
+    let _store = start();
+    setup();
+    let p = player();
+    assert_eq!(p.data.ticket, get_initial_tickets());
+    assert_eq!(p.data.balance, 50);
+    fails(&PLAYER, Command::InstallPlayer(None), PuppyError::PlayerAlreadyExist);
+    fails(&ADMIN_PUBKEY, Command::InstallMeme(MEME), PuppyError::MemeAlreadyExist);
+    fails(&[9, 9, 9, 9], Command::InstallPlayer(Some([5, 5])), PuppyError::ReferrerNotExist);
+}
+
+#[test]
+fn vote() {
+    let _store = start();
+    setup();
+    tick(2);
+    ok(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)));
+    fails(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)), PuppyError::PlayerActionNotFinished);
+    tick(2);
+    ok(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)));
+    tick(2);
+    fails(&PLAYER, Command::Activity(Activity::Vote(8)), PuppyError::InvalidMemeIndex);
+    let p = player();
+    assert_eq!(p.data.ticket, get_initial_tickets() - 2);
+    assert_eq!(p.data.progress, 100);
+    assert_eq!(MemeInfo::get_object(MEME).unwrap().data.rank, 2);
+}
+
+#[test]
+fn season() {
+    let _store = start();
+    setup();
+    tick(2);
+    ok(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)));
+    ok(&ADMIN_PUBKEY, Command::Season(SeasonCommand::Open));
+    tick(2);
+    ok(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)));
+    ok(&ADMIN_PUBKEY, Command::Season(SeasonCommand::Close));
+
+    // the season rank is archived and reset, the all time rank keeps paying yield
+    let archived = SeasonMemeInfo::get_object(SeasonMemeInfo::index(1, MEME)).unwrap().data;
+    assert_eq!(archived.rank, 1);
+    let meme = MemeInfo::get_object(MEME).unwrap().data;
+    assert_eq!((meme.rank, meme.season_rank), (2, 0));
+    tick(2);
+    fails(&PLAYER, Command::Activity(Activity::Vote(MEME as usize)), PuppyError::SeasonNotOpen);
+}
+
+#[test]
+fn stake_collect_unstake() {
+    let _store = start();
+    setup();
+    ok(&PLAYER, Command::Activity(Activity::Stake(MEME as usize, 10)));
+    let meme = MemeInfo::get_object(MEME).unwrap().data;
+    assert_eq!((meme.owner, meme.stake), (PID, 10));
+    assert_eq!(player().data.ticket, get_initial_tickets() - 10);
+
+    tick(10);
+    ok(&PLAYER, Command::Activity(Activity::Collect(MEME as usize)));
+    let pos = StakeInfo::get_position(&PID, MEME).unwrap().data;
+    assert_eq!((pos.stake, pos.timestamp), (10, 10));
+
+    fails(&PLAYER, Command::Activity(Activity::Unstake(MEME as usize, 11)), PuppyError::PlayerNotEnoughStake);
+    ok(&PLAYER, Command::Activity(Activity::Unstake(MEME as usize, 10)));
+    let meme = MemeInfo::get_object(MEME).unwrap().data;
+    assert_eq!((meme.owner, meme.stake), ([0, 0], 0));
+    assert_eq!(player().data.ticket, get_initial_tickets());
+    fails(&[5, 5, 5, 5], Command::Activity(Activity::Collect(MEME as usize)), PuppyError::PlayerNotExist);
+}
+
+#[test]
+fn lottery() {
+    let _store = start();
+    setup();
+    fill_progress();
+    fails(&PLAYER, Command::Activity(Activity::Lottery), PuppyError::LotterySeedNotCommitted);
+
+    let seed = [11, 12, 13, 14];
+    let digest = Sha256::digest(seed.iter().flat_map(|w: &u64| w.to_le_bytes()).collect::<Vec<u8>>());
+    let mut hash = [0u64; 4];
+    for (i, chunk) in digest.chunks(8).enumerate() {
+        hash[i] = u64::from_le_bytes(chunk.try_into().unwrap());
+    }
+    ok(&ADMIN_PUBKEY, Command::Seed(SeedCommand::Commit(hash)));
+    ok(&PLAYER, Command::Activity(Activity::Lottery));
+    assert_eq!(player().data.progress, 0);
+    assert_eq!(LotteryDraw::get_position(&PID, 0).unwrap().data.epoch, 1);
+    fails(&PLAYER, Command::Activity(Activity::Lottery), PuppyError::LotterySeedNotRevealed);
+
+    fails(&ADMIN_PUBKEY, Command::Seed(SeedCommand::Reveal([0; 4])), PuppyError::LotterySeedMismatch);
+    ok(&ADMIN_PUBKEY, Command::Seed(SeedCommand::Reveal(seed)));
+    let before = player().data;
+    let r = ok(&PLAYER, Command::Activity(Activity::Lottery));
+    let after = player().data;
+    assert_eq!(LotteryDraw::get_position(&PID, 0).unwrap().data.epoch, 0);
+
+    // [pid_1, pid_2, tier, reward kind, amount], the player has no referrer
+    let prize = event(&r, EVENT_LOTTERY_PRIZE).unwrap();
+    assert_eq!([prize[0], prize[1]], PID);
+    let gained = match prize[3] {
+        0 | 3 => after.balance - before.balance,
+        1 => after.lottery_info - before.lottery_info,
+        _ => after.ticket - before.ticket,
+    };
+    assert_eq!(gained, prize[4]);
+    fails(&PLAYER, Command::Activity(Activity::Lottery), PuppyError::PlayerLotteryProgressNotFull);
+}
+
+#[test]
+fn withdraw() {
+    let _store = start();
+    setup();
+    fails(&PLAYER, Command::Withdraw(Withdraw { data: [51, 0, 0] }), PuppyError::PlayerNotEnoughBalance);
+    ok(&PLAYER, Command::Withdraw(Withdraw { data: [20, 0xabcd, 0xef01] }));
+    assert_eq!(player().data.balance, 30);
+    fails(&[5, 5, 5, 5], Command::Withdraw(Withdraw { data: [1, 0, 0] }), PuppyError::PlayerNotExist);
+}
+
+#[test]
+fn pause() {
+    let _store = start();
+    setup();
+    let withdraw = Command::Withdraw(Withdraw { data: [1, 0, 0] });
+    let paused = 1 << withdraw.opcode();
+    let r = ok(&ADMIN_PUBKEY, Command::SetPause(SetPause { paused }));
+    assert_eq!(event(&r, EVENT_PAUSE_UPDATE), Some(vec![0, paused]));
+    fails(&PLAYER, withdraw.clone(), PuppyError::CommandPaused);
+    let r = ok(&ADMIN_PUBKEY, Command::SetPause(SetPause { paused: 0 }));
+    assert_eq!(event(&r, EVENT_PAUSE_UPDATE), Some(vec![paused, 0]));
+    ok(&PLAYER, withdraw);
+}
+
+#[test]
+fn preempt_after_flush() {
+    let _store = start();
+    ok(&ADMIN_PUBKEY, Command::InstallPlayer(None));
+    tick(1);
+    for i in 0..300 {
+        ok(&[i, 100 + i, 200 + i, 0], Command::InstallPlayer(None));
+    }
+    assert!(GlobalState::preempt());
+    GlobalState::flush_settlement();
+    assert!(!GlobalState::preempt());
+    let r = ok(&PLAYER, Command::InstallPlayer(None));
+    assert_eq!(r[1], 1);
+}
+
+#[test]
+fn collect_large_yield() {
+    let _store = start();
+    setup();
+    ok(&PLAYER, Command::Activity(Activity::Stake(MEME as usize, 40)));
+    // a rank whose product with the stake and elapsed ticks overflows u64
+    let mut meme = MemeInfo::get_object(MEME).unwrap();
+    meme.data.rank = 1 << 60;
+    meme.store();
+    tick(10);
+    ok(&PLAYER, Command::Activity(Activity::Collect(MEME as usize)));
+    assert_eq!(player().data.balance, 50 + (10u128 * 40 * (1 << 60) / 10000000) as u64);
+    ok(&PLAYER, Command::Activity(Activity::Unstake(MEME as usize, 40)));
+}
@@ -0,0 +1,138 @@
+// Generated by `cargo run --features native --bin ts_bindings`, do not edit.
+
+// command opcodes
+export const TICK = 0n;
+export const INSTALL_PLAYER = 1n;
+export const VOTE = 2n;
+export const STAKE = 3n;
+export const COLLECT = 4n;
+export const COMMENT = 5n;
+export const LOTTERY = 6n;
+export const INSTALL_MEME = 7n;
+export const WITHDRAW = 8n;
+export const DEPOSIT = 9n;
+export const WITHDRAW_LOTTERY = 10n;
+export const OPEN_SEASON = 11n;
+export const CLOSE_SEASON = 12n;
+export const UNSTAKE = 13n;
+export const COMMIT_SEED = 14n;
+export const REVEAL_SEED = 15n;
+export const BUY_TICKETS = 16n;
+export const CHECK_IN = 17n;
+export const SHAKE_FEET = 18n;
+export const SHAKE_HEAD = 19n;
+export const JUMP = 20n;
+export const UPDATE_CONFIG = 21n;
+export const GRANT_ROLE = 22n;
+export const REVOKE_ROLE = 23n;
+export const SET_PAUSE = 24n;
+
+// event ids
+export const EVENT_POSITION_UPDATE = 1;
+export const EVENT_MEME_UPDATE = 2;
+export const EVENT_SEASON_MEME = 3;
+export const EVENT_COMMENT = 4;
+export const EVENT_SEED_COMMITMENT = 5;
+export const EVENT_LOTTERY_DRAW = 6;
+export const EVENT_LOTTERY_PRIZE = 7;
+export const EVENT_REFERRAL = 8;
+export const EVENT_REFERRAL_REWARD = 9;
+export const EVENT_CONFIG_UPDATE = 10;
+export const EVENT_ROLE_UPDATE = 11;
+export const EVENT_PAUSE_UPDATE = 13;
+
+// role ids granted with GRANT_ROLE
+export const ROLE_SUPER_ADMIN = 0n;
+export const ROLE_TICKER = 1n;
+export const ROLE_DEPOSITOR = 2n;
+export const ROLE_MEME_CURATOR = 3n;
+export const ROLE_MODERATOR = 4n;
+export const ROLE_LOTTERY_OPERATOR = 5n;
+export const ROLE_OPERATOR = 6n;
+
+// error codes returned as the first word of a transaction result
+export const ERROR_PLAYER_ALREADY_EXIST = 1;
+export const ERROR_PLAYER_NOT_EXIST = 2;
+export const ERROR_PLAYER_NOT_SELECTED = 3;
+export const ERROR_SELECTED_PLAYER_NOT_EXIST = 4;
+export const ERROR_PLAYER_ACTION_NOT_FINISHED = 5;
+export const ERROR_PLAYER_LOTTERY_EXPIRED = 6;
+export const ERROR_PLAYER_LOTTERY_PROGRESS_NOT_FULL = 7;
+export const ERROR_PLAYER_NOT_ENOUGH_TICKET = 8;
+export const ERROR_SPECIFIED_MEME_INDEX_NOT_FOUND = 9;
+export const ERROR_NOTHING_TO_COLLECT = 10;
+export const ERROR_SEASON_ALREADY_OPEN = 11;
+export const ERROR_SEASON_NOT_OPEN = 12;
+export const ERROR_PLAYER_NOT_ENOUGH_STAKE = 13;
+export const ERROR_INVALID_COMMENT = 14;
+export const ERROR_MEME_ALREADY_EXIST = 15;
+export const ERROR_LOTTERY_SEED_NOT_COMMITTED = 16;
+export const ERROR_LOTTERY_SEED_NOT_REVEALED = 17;
+export const ERROR_LOTTERY_SEED_ALREADY_REVEALED = 18;
+export const ERROR_LOTTERY_SEED_MISMATCH = 19;
+export const ERROR_OVERFLOW = 20;
+export const ERROR_PLAYER_NOT_ENOUGH_BALANCE = 21;
+export const ERROR_PLAYER_CHECK_IN_NOT_READY = 22;
+export const ERROR_REFERRER_NOT_EXIST = 23;
+export const ERROR_CONFIG_VALUE_OUT_OF_RANGE = 24;
+export const ERROR_COMMAND_PAUSED = 25;
+
+export const ERROR_NAMES: Record<number, string> = {
+  1: "PlayerAlreadyExist",
+  2: "PlayerNotExist",
+  3: "PlayerNotSelected",
+  4: "SelectedPlayerNotExist",
+  5: "PlayerActionNotFinished",
+  6: "PlayerLotteryExpired",
+  7: "PlayerLotteryProgressNotFull",
+  8: "PlayerNotEnoughTicket",
+  9: "SpecifiedMemeIndexNotFound",
+  10: "NothingToCollect",
+  11: "SeasonAlreadyOpen",
+  12: "SeasonNotOpen",
+  13: "PlayerNotEnoughStake",
+  14: "InvalidComment",
+  15: "MemeAlreadyExist",
+  16: "LotterySeedNotCommitted",
+  17: "LotterySeedNotRevealed",
+  18: "LotterySeedAlreadyRevealed",
+  19: "LotterySeedMismatch",
+  20: "Overflow",
+  21: "PlayerNotEnoughBalance",
+  22: "PlayerCheckInNotReady",
+  23: "ReferrerNotExist",
+  24: "ConfigValueOutOfRange",
+  25: "CommandPaused",
+};
+
+export const ERROR_MESSAGES: Record<number, string> = {
+  1: "player already exists",
+  2: "player does not exist",
+  3: "player is not selected",
+  4: "selected player does not exist",
+  5: "previous action has not finished yet",
+  6: "lottery redemption window has expired",
+  7: "lottery progress is not full",
+  8: "not enough tickets",
+  9: "meme does not exist",
+  10: "no stake position to collect from",
+  11: "a season is already open",
+  12: "no season is open",
+  13: "not enough stake in the position",
+  14: "comment is empty, too long, not utf8 or has a zero byte",
+  15: "meme already exists",
+  16: "no lottery seed is committed for this epoch",
+  17: "lottery seed has not been revealed yet",
+  18: "lottery seed is already revealed",
+  19: "revealed seed does not match the commitment",
+  20: "amount overflows",
+  21: "not enough balance",
+  22: "already checked in during this interval",
+  23: "referrer does not exist",
+  24: "config value is out of the allowed range",
+  25: "command is paused by the admin",
+};
+
+// word layout of the object data carried by events
+export const MEME_INFO_FIELDS = ["layout", "id", "rank", "stake", "owner_1", "owner_2", "comments", "season_rank"];
+export const STAKE_INFO_FIELDS = ["layout", "stake", "timestamp"];
@@ -48,13 +48,26 @@ export class IndexedObject {
     }
 }
 
+// data[0] is the storage layout tag (see MEME_INFO_FIELDS and STAKE_INFO_FIELDS),
+// untagged (version 0) records start with their first field
+const LAYOUT_MAGIC = 0x70657473n;
+
+function layoutFields(data: bigint[]): bigint[] {
+  if (data.length > 0 && (BigInt(data[0]) >> 32n) == LAYOUT_MAGIC) {
+    return data.slice(1);
+  }
+  return data;
+}
+
 export function parseMemeInfo(iobj: IndexedObject) {
   console.log(iobj);
+  const data = layoutFields(iobj.data);
   return  {
     id: Number(iobj.index),
-    rank: Number(iobj.data[1]),
-    stake: Number(iobj.data[2]),
-    pid: [Number(iobj.data[3]), Number(iobj.data[4])]
+    rank: Number(data[1]),
+    stake: Number(data[2]),
+    pid: [Number(data[3]), Number(data[4])],
+    season_rank: Number(data[6] ?? 0n),
   }
 }
 
@@ -91,7 +104,8 @@ export class Position {
                 return ret;
             }
         });
-        return new Position(obj.pid_1, obj.pid_2, obj.object_index, obj.data);
+        // rows indexed before the tag was stripped still carry it
+        return new Position(obj.pid_1, obj.pid_2, obj.object_index, layoutFields(obj.data));
     }
 
     toMongooseDoc(): mongoose.Document {
@@ -135,7 +149,8 @@ export class Position {
     }
     static fromEvent(data: BigUint64Array): Position {
         let bigintarray:bigint[] = Array.from(data);
-        return new Position(data[0], data[1], data[2], bigintarray.slice(3));
+        // data is [stake, timestamp] without the layout tag of StakeInfo
+        return new Position(data[0], data[1], data[2], layoutFields(bigintarray.slice(3)));
     }
 
 }
@@ -168,3 +183,129 @@ PositionSchema.index(
 
 export const PositionModel = mongoose.model('Position', PositionSchema);
 
+
+export class Comment {
+    index: bigint;
+    pid_1: bigint;
+    pid_2: bigint;
+    meme: bigint;
+    timestamp: bigint;
+    content: string;
+    constructor(index: bigint, pid_1: bigint, pid_2: bigint, meme: bigint, timestamp: bigint, content: string) {
+        this.index = index;
+        this.pid_1 = pid_1;
+        this.pid_2 = pid_2;
+        this.meme = meme;
+        this.timestamp = timestamp;
+        this.content = content;
+    }
+
+    static fromMongooseDoc(doc: mongoose.Document): Comment {
+        const obj = doc.toObject({
+            transform: (doc, ret) => {
+                delete ret._id;
+                return ret;
+            }
+        });
+        return new Comment(obj.index, obj.pid_1, obj.pid_2, obj.meme, obj.timestamp, obj.content);
+    }
+
+    toObject(): { index: bigint, pid_1: bigint, pid_2: bigint, meme: bigint, timestamp: bigint, content: string } {
+        return {
+            index: this.index,
+            pid_1: this.pid_1,
+            pid_2: this.pid_2,
+            meme: this.meme,
+            timestamp: this.timestamp,
+            content: this.content,
+        };
+    }
+
+    toJSON() {
+        return {
+            index: this.index.toString(),
+            pid: [this.pid_1.toString(), this.pid_2.toString()],
+            meme: this.meme.toString(),
+            timestamp: this.timestamp.toString(),
+            content: this.content,
+        };
+    }
+
+    // event layout: [index, pid_1, pid_2, meme, timestamp, len, ...content words]
+    static fromEvent(data: BigUint64Array): Comment {
+        const len = Number(data[5]);
+        const words = data.slice(6);
+        const bytes = Buffer.from(words.buffer, words.byteOffset, words.byteLength).subarray(0, len);
+        return new Comment(data[0], data[1], data[2], data[3], data[4], bytes.toString("utf8"));
+    }
+}
+
+const CommentSchema = new mongoose.Schema({
+    index: { type: BigInt, required: true, unique: true },
+    pid_1: { type: BigInt, required: true },
+    pid_2: { type: BigInt, required: true },
+    meme: { type: BigInt, required: true },
+    timestamp: { type: BigInt, required: true },
+    content: { type: String, required: true },
+});
+
+CommentSchema.index({ meme: 1, index: 1 });
+
+export const CommentModel = mongoose.model('Comment', CommentSchema);
+
+
+export class Referral {
+    referrer_1: bigint;
+    referrer_2: bigint;
+    pid_1: bigint;
+    pid_2: bigint;
+    constructor(referrer_1: bigint, referrer_2: bigint, pid_1: bigint, pid_2: bigint) {
+        this.referrer_1 = referrer_1;
+        this.referrer_2 = referrer_2;
+        this.pid_1 = pid_1;
+        this.pid_2 = pid_2;
+    }
+
+    static fromMongooseDoc(doc: mongoose.Document): Referral {
+        const obj = doc.toObject({
+            transform: (doc, ret) => {
+                delete ret._id;
+                return ret;
+            }
+        });
+        return new Referral(obj.referrer_1, obj.referrer_2, obj.pid_1, obj.pid_2);
+    }
+
+    toObject(): { referrer_1: bigint, referrer_2: bigint, pid_1: bigint, pid_2: bigint } {
+        return {
+            referrer_1: this.referrer_1,
+            referrer_2: this.referrer_2,
+            pid_1: this.pid_1,
+            pid_2: this.pid_2,
+        };
+    }
+
+    toJSON() {
+        return {
+            referrer: [this.referrer_1.toString(), this.referrer_2.toString()],
+            pid: [this.pid_1.toString(), this.pid_2.toString()],
+        };
+    }
+
+    // event layout: [referrer_1, referrer_2, pid_1, pid_2, bonus]
+    static fromEvent(data: BigUint64Array): Referral {
+        return new Referral(data[0], data[1], data[2], data[3]);
+    }
+}
+
+const ReferralSchema = new mongoose.Schema({
+    referrer_1: { type: BigInt, required: true },
+    referrer_2: { type: BigInt, required: true },
+    pid_1: { type: BigInt, required: true },
+    pid_2: { type: BigInt, required: true },
+});
+
+ReferralSchema.index({ pid_1: 1, pid_2: 1 }, { unique: true });
+ReferralSchema.index({ referrer_1: 1, referrer_2: 1 });
+
+export const ReferralModel = mongoose.model('Referral', ReferralSchema);
@@ -8,11 +8,8 @@ import dotenv from "dotenv";
 import { createClient, SanityClient } from "@sanity/client";
 import { Player } from "./api.js";
 import { IndexedObject, IndexedObjectModel, parseMemeInfo } from "./info.js";
+import { INSTALL_PLAYER, INSTALL_MEME, WITHDRAW, DEPOSIT } from "./bindings.js";
 
-const INSTALL_PLAYER = 1n;
-const INSTALL_MEME = 7n;
-const WITHDRAW = 8n;
-const DEPOSIT = 9n;
 let account = "1234";
 const rpc: any = new ZKWasmAppRpc("http://127.0.0.1:3000");
 let player = new Player(account, rpc, DEPOSIT, WITHDRAW);
@@ -104,12 +101,14 @@ export class SanityService {
     // );
 
     // install memes that are not in MongoDB
+    // InstallMeme is nonce checked and rejects existing ids, so install one by one
     if (currentSeason) {
-      currentSeason.memes.forEach((meme) => {
+      for (const meme of currentSeason.memes) {
         // if (!idSet.has(meme.id)) {
-          player.runCommand(INSTALL_MEME, this.nonce, [BigInt(meme.id)]);
+          this.nonce = await player.getNonce();
+          await player.runCommand(INSTALL_MEME, this.nonce, [BigInt(meme.id)]);
         // }
-      });
+      }
     }
   }
 
@@ -171,6 +170,7 @@ export class SanityService {
           };
 
           const createdDocument = await this.sanityClient.create(newDocument);
+          this.nonce = await player.getNonce();
           await player.runCommand(INSTALL_MEME, this.nonce, [BigInt(id)]);
 
           fs.unlinkSync(avatarFile.path);
@@ -1,10 +1,11 @@
 import { Service } from "zkwasm-ts-server";
 import {TxWitness} from "zkwasm-ts-server/src/prover";
 import {Event, EventModel} from "zkwasm-ts-server";
-import { Position, IndexedObjectModel, IndexedObject, PositionModel, parseMemeInfo} from "./info.js";
+import { Position, IndexedObjectModel, IndexedObject, PositionModel, parseMemeInfo, Comment, CommentModel, Referral, ReferralModel} from "./info.js";
 import { Express } from "express";
 //import {clearTxFromCommit, CommitModel, getTxFromCommit, insertTxIntoCommit} from "./commits.js";
 import { SanityService } from "./sanity_service.js";
+import { EVENT_POSITION_UPDATE, EVENT_MEME_UPDATE, EVENT_COMMENT, EVENT_LOTTERY_PRIZE, EVENT_REFERRAL, EVENT_REFERRAL_REWARD, EVENT_CONFIG_UPDATE, EVENT_ROLE_UPDATE, EVENT_PAUSE_UPDATE, ERROR_NAMES } from "./bindings.js";
 
 const uploadDir = "./uploads";
 const sanityService = new SanityService(uploadDir);
@@ -40,6 +41,25 @@ function extra (app: Express) {
             data: jdoc,
         });
     });
+    app.get('/data/comments/:meme', async(req:any, res) => {
+        let meme:bigint = BigInt(req.params.meme);
+        const doc = await CommentModel.find({meme: meme}).sort({index: 1});
+        const data = doc.map((d) => Comment.fromMongooseDoc(d).toJSON());
+        res.status(201).send({
+            success: true,
+            data: data,
+        });
+    });
+    app.get('/data/referrals/:pid1/:pid2', async(req:any, res) => {
+        let pid1:bigint = BigInt(req.params.pid1);
+        let pid2:bigint = BigInt(req.params.pid2);
+        const doc = await ReferralModel.find({referrer_1: pid1, referrer_2: pid2});
+        const data = doc.map((d) => Referral.fromMongooseDoc(d).toJSON());
+        res.status(201).send({
+            success: true,
+            data: data,
+        });
+    });
     sanityService.registerAPICallback(app);
 }
 
@@ -48,8 +68,6 @@ await service.serve();
 await sanityService.init();
 await sanityService.setMemeList();
 
-const EVENT_POSITION_UPDATE = 1;
-const EVENT_MEME_UPDATE = 2;
 
 async function batchedCallback(arg: TxWitness[], preMerkle: string, postMerkle: string) {
 	/*
@@ -69,7 +87,7 @@ async function eventCallback(arg: TxWitness, data: BigUint64Array) {
 
 	//console.log("eventCallback", arg, data);
 	if(data[0] != 0n) {
-		console.log("non-zero return, tx failed", data[0]);
+		console.log("non-zero return, tx failed", data[0], ERROR_NAMES[Number(data[0])]);
 		return;
 	}
 	if(data.length <= 2) {
@@ -120,6 +138,51 @@ async function eventCallback(arg: TxWitness, data: BigUint64Array) {
 					console.log("save token", doc);
 				}
 				break;
+			case EVENT_COMMENT:
+				{
+					console.log("comment event");
+					let comment = Comment.fromEvent(eventData);
+					let doc = await CommentModel.findOneAndUpdate({index: comment.index}, comment.toObject(), {upsert: true});
+					console.log("save comment", comment.meme, comment.index);
+				}
+				break;
+			case EVENT_LOTTERY_PRIZE:
+				{
+					// [pid_1, pid_2, tier, reward kind, amount]
+					console.log("lottery prize", eventData[0], eventData[1], "tier", eventData[2], "kind", eventData[3], "amount", eventData[4]);
+				}
+				break;
+			case EVENT_REFERRAL:
+				{
+					let referral = Referral.fromEvent(eventData);
+					let doc = await ReferralModel.findOneAndUpdate({pid_1: referral.pid_1, pid_2: referral.pid_2}, referral.toObject(), {upsert: true});
+					console.log("save referral", referral.referrer_1, referral.referrer_2, "->", referral.pid_1, referral.pid_2);
+				}
+				break;
+			case EVENT_REFERRAL_REWARD:
+				{
+					// [referrer_1, referrer_2, pid_1, pid_2, reward kind, amount]
+					console.log("referral reward", eventData[0], eventData[1], "from", eventData[2], eventData[3], "kind", eventData[4], "amount", eventData[5]);
+				}
+				break;
+			case EVENT_CONFIG_UPDATE:
+				{
+					// [param, old value, new value]
+					console.log("config update", eventData[0], eventData[1], "->", eventData[2]);
+				}
+				break;
+			case EVENT_ROLE_UPDATE:
+				{
+					// [pid_1, pid_2, 0, role bitmap]
+					console.log("role update", eventData[0], eventData[1], "roles", eventData[3]);
+				}
+				break;
+			case EVENT_PAUSE_UPDATE:
+				{
+					// [old paused bitmap, new paused bitmap]
+					console.log("pause update", eventData[0], "->", eventData[1]);
+				}
+				break;
 			default:
 				console.log("unknown event");
 				break;
@@ -2,17 +2,8 @@ import { Player } from "./api.js";
 //import { LeHexBN, ZKWasmAppRpc} from "zkwasm-minirollup-rpc";
 import { LeHexBN, query, ZKWasmAppRpc} from "zkwasm-ts-server";
 import { createAsyncThunk } from '@reduxjs/toolkit';
+import { INSTALL_PLAYER, VOTE, STAKE, COLLECT, COMMENT, INSTALL_MEME, WITHDRAW, DEPOSIT, UNSTAKE, BUY_TICKETS, CHECK_IN, SHAKE_FEET, JUMP } from "./bindings.js";
 
-const INSTALL_PLAYER = 1n;
-const VOTE = 2n;
-const STAKE = 3n;
-const COLLECT = 4n;
-const COMMENT = 5n;
-const LOTTERY = 6n;
-const INSTALL_MEME = 7n;
-const WITHDRAW = 8n;
-const DEPOSIT = 9n;
-const WITHDRAW_LOTTERY = 10n;
 
 
 
@@ -69,6 +60,26 @@ async function main() {
     nonce = await player.getNonce();
     await player.runCommand(COLLECT, nonce, [1n]);
 
+    nonce = await player.getNonce();
+    await player.runCommand(UNSTAKE, nonce, [1n, 1n]);
+
+    nonce = await player.getNonce();
+    await player.runCommand(CHECK_IN, nonce, []);
+
+    nonce = await player.getNonce();
+    await player.runCommand(SHAKE_FEET, nonce, []);
+    await delay(10000); // Wait for 10 seconds/2 ticks
+
+    nonce = await player.getNonce();
+    await player.runCommand(JUMP, nonce, []);
+
+    nonce = await player.getNonce();
+    await player.runCommand(BUY_TICKETS, nonce, [2n]);
+
+    // "hello" packed little endian into one word
+    nonce = await player.getNonce();
+    await player.runCommand(COMMENT, nonce, [1n, 0x6f6c6c6568n]);
+
 
     data = await player.rpc.queryData(`position/${pubkey[1]}/${pubkey[2]}`)
     console.log(data);